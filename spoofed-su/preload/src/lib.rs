@@ -0,0 +1,1285 @@
+mod real;
+
+use minsuki::caps;
+use minsuki::emulate;
+use minsuki::paths;
+use minsuki::stat;
+use minsuki::state::StateManager;
+use minsuki::trace::{Disposition, TraceArgs, TraceEvent, TraceLog};
+use minsuki::types::{Credentials, FakeMetadata, Result, CREDENTIALS_ENV, ROOTFS_ENV};
+use lazy_static::lazy_static;
+use minsuki::userdb::{Group, User, UserDb};
+use std::cell::{Cell, RefCell};
+use std::ffi::{c_void, CStr, CString};
+use std::os::raw::{c_char, c_int, c_uint, c_ulong};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread::LocalKey;
+
+lazy_static! {
+    static ref STATE_MANAGER: Mutex<Option<StateManager>> = Mutex::new(None);
+    static ref CREDENTIALS: Mutex<Option<Credentials>> = Mutex::new(None);
+    static ref TRACE_LOG: Option<TraceLog> = std::env::var_os("MINSUKI_TRACE")
+        .and_then(|path| TraceLog::open(Path::new(&path)).ok());
+    static ref USER_DB: Option<UserDb> = std::env::var_os(ROOTFS_ENV)
+        .map(|root| busy(|| UserDb::load(Path::new(&root))));
+}
+
+thread_local! {
+    /// Set while this thread runs the library's own code, whose use of std
+    /// stats files through the wrappers below
+    static BUSY: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Run `f` with the stat wrappers of this thread passing straight through
+/// to libc, so that loading the state does not look itself up
+fn busy<T>(f: impl FnOnce() -> T) -> T {
+    let was_busy = BUSY.try_with(|busy| busy.replace(true)).unwrap_or(true);
+    let result = f();
+    if !was_busy {
+        let _ = BUSY.try_with(|busy| busy.set(false));
+    }
+    result
+}
+
+/// Whether this thread is inside `busy`, or tearing down its thread locals
+fn is_busy() -> bool {
+    BUSY.try_with(Cell::get).unwrap_or(true)
+}
+
+fn init_state_manager() {
+    let mut manager = STATE_MANAGER.lock().unwrap();
+    if manager.is_none() {
+        let state_file = std::env::var("MINSUKI_STATE")
+            .unwrap_or_else(|_| "/tmp/minsuki.state".to_string());
+        *manager = busy(|| StateManager::new(&state_file).ok());
+    }
+}
+
+fn get_state_manager() -> Option<StateManager> {
+    init_state_manager();
+    STATE_MANAGER.lock().unwrap().clone()
+}
+
+/// Run `f` on the fake identity of this process. It starts out as the
+/// session's starting identity; forked children get a copy along with the
+/// rest of our memory, and it is kept in the environment to survive execve
+fn with_credentials<T>(f: impl FnOnce(&mut Credentials) -> T) -> Option<T> {
+    let mut credentials = CREDENTIALS.lock().unwrap();
+    if credentials.is_none() {
+        *credentials = std::env::var(CREDENTIALS_ENV)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .or_else(|| get_state_manager().map(|manager| manager.get_state().lock().unwrap().credentials.clone()));
+    }
+    
+    let creds = credentials.as_mut()?;
+    let before = creds.clone();
+    let result = f(creds);
+    if *creds != before {
+        if let Ok(json) = serde_json::to_string(creds) {
+            std::env::set_var(CREDENTIALS_ENV, json);
+        }
+    }
+    Some(result)
+}
+
+/// Apply the execve that loaded this copy of the library to the fake
+/// identity: the program's fake set-user-ID and set-group-ID bits and
+/// capabilities count, as they would for a real exec
+extern "C" fn on_exec() {
+    // Only in a process of a session, not wherever the library is linked
+    if std::env::var_os("MINSUKI_STATE").is_none() {
+        return;
+    }
+    let path = match std::fs::read_link("/proc/self/exe") {
+        Ok(path) => paths::canonicalize(&path, true),
+        Err(_) => return,
+    };
+    let meta = match get_state_manager() {
+        Some(manager) => manager.get_state().lock().unwrap().get_metadata(&path).cloned(),
+        None => return,
+    };
+    let meta = match meta.or_else(|| busy(|| std::fs::metadata(&path)).ok().map(|real| FakeMetadata::from(&real))) {
+        Some(meta) => meta,
+        None => return,
+    };
+    with_credentials(|c| c.exec(&meta));
+}
+
+/// Every dynamically linked program runs the constructors of its preloaded
+/// libraries once per execve, before its own code
+#[used]
+#[link_section = ".init_array"]
+static ON_EXEC: extern "C" fn() = on_exec;
+
+// Helper function to convert C string to the canonical PathBuf the state is keyed on
+unsafe fn cstr_to_pathbuf(path: *const c_char, follow: bool) -> Option<PathBuf> {
+    if path.is_null() {
+        return None;
+    }
+    CStr::from_ptr(path)
+        .to_str()
+        .ok()
+        .map(|s| paths::canonicalize(Path::new(s), follow))
+}
+
+/// The file an open descriptor refers to, if it is one with a path
+fn fd_path(fd: c_int) -> Option<PathBuf> {
+    let path = std::fs::read_link(format!("/proc/self/fd/{}", fd)).ok()?;
+    // Pipes, sockets and anonymous inodes read as `type:[inode]`
+    path.is_absolute().then_some(path)
+}
+
+/// Resolve the path of an *at call against its directory descriptor. An
+/// empty path with AT_EMPTY_PATH names the descriptor itself
+unsafe fn at_to_pathbuf(dirfd: c_int, path: *const c_char, flags: c_int) -> Option<PathBuf> {
+    let path = if path.is_null() { "" } else { CStr::from_ptr(path).to_str().ok()? };
+    if path.is_empty() {
+        return if flags & libc::AT_EMPTY_PATH != 0 { fd_path(dirfd) } else { None };
+    }
+    let path = Path::new(path);
+    let path = if path.is_absolute() || dirfd == libc::AT_FDCWD { path.to_path_buf() } else { fd_path(dirfd)?.join(path) };
+    Some(paths::canonicalize(&path, flags & libc::AT_SYMLINK_NOFOLLOW == 0))
+}
+
+/// Record a change to the fake metadata of `path`, starting from the real
+/// file when nothing is recorded for it yet. Fails for a file that does not
+/// exist, leaving the error to the real call
+fn update_metadata(path: PathBuf, change: impl FnOnce(&mut FakeMetadata, &std::fs::Metadata)) -> bool {
+    busy(|| {
+        let (real, manager) = match (std::fs::symlink_metadata(&path), get_state_manager()) {
+            (Ok(real), Some(manager)) => (real, manager),
+            _ => return false,
+        };
+        let known = manager.get_state().lock().unwrap().get_metadata(&path).cloned();
+        let mut meta = known.unwrap_or_else(|| FakeMetadata::from(&real));
+        change(&mut meta, &real);
+        manager.set_metadata(path, meta).is_ok()
+    })
+}
+
+/// Record an intercepted call in the session's trace log, if there is one
+fn trace(function: &str, args: TraceArgs, fake_result: Option<i64>, real_result: Option<i64>) {
+    if let Some(trace) = TRACE_LOG.as_ref() {
+        trace.record(&TraceEvent {
+            pid: std::process::id() as i32,
+            syscall: function.to_string(),
+            args,
+            disposition: if fake_result.is_some() { Disposition::Emulated } else { Disposition::Passthrough },
+            fake_result,
+            real_result,
+        });
+    }
+}
+
+/// Trace the result of a call passed on to libc, keeping its errno intact
+fn passthrough(function: &str, args: TraceArgs, ret: c_int) -> c_int {
+    let errno = std::io::Error::last_os_error();
+    let real = if ret < 0 { -(errno.raw_os_error().unwrap_or(0) as i64) } else { ret as i64 };
+    trace(function, args, None, Some(real));
+    if ret < 0 {
+        real::set_errno(errno.raw_os_error().unwrap_or(0));
+    }
+    ret
+}
+
+/// Fail an emulated call with `errno`, the way libc reports errors
+fn fail(function: &str, args: TraceArgs, errno: c_int) -> c_int {
+    trace(function, args, Some(-(errno as i64)), None);
+    real::set_errno(errno);
+    -1
+}
+
+/// Finish an emulated credential change; a refused transition fails with
+/// EPERM
+fn credential_result(function: &str, args: TraceArgs, result: Result<()>) -> c_int {
+    match result {
+        Ok(()) => {
+            trace(function, args, Some(0), None);
+            0
+        }
+        Err(_) => fail(function, args, libc::EPERM),
+    }
+}
+
+/// Whether the fake identity is refused a change only root or holders of
+/// `capability` may make. Without a session there is nothing to refuse
+fn denied(capability: caps::Capability) -> bool {
+    with_credentials(|c| !c.is_root() && !c.capable(capability)) == Some(true)
+}
+
+/// Intercept chown system call
+#[no_mangle]
+pub unsafe extern "C" fn chown(path: *const c_char, uid: libc::uid_t, gid: libc::gid_t) -> c_int {
+    log::debug!("Intercepted chown: uid={}, gid={}", uid, gid);
+    
+    let pathbuf = cstr_to_pathbuf(path, true);
+    let args = TraceArgs { path: pathbuf.clone(), uid: Some(uid), gid: Some(gid), ..Default::default() };
+    if let Some(pathbuf) = pathbuf {
+        if denied(caps::Capability::Chown) {
+            return fail("chown", args, libc::EPERM);
+        }
+        if update_metadata(pathbuf, |meta, real| meta.chown(uid, gid, real.is_dir())) {
+            log::info!("Emulated chown successfully");
+            trace("chown", args, Some(0), None);
+            return 0; // Success
+        }
+    }
+    
+    // Fall back to real chown (will likely fail without root)
+    passthrough("chown", args, real::chown(path, uid, gid))
+}
+
+/// Intercept lchown system call
+#[no_mangle]
+pub unsafe extern "C" fn lchown(path: *const c_char, uid: libc::uid_t, gid: libc::gid_t) -> c_int {
+    log::debug!("Intercepted lchown: uid={}, gid={}", uid, gid);
+    
+    let pathbuf = cstr_to_pathbuf(path, false);
+    let args = TraceArgs { path: pathbuf.clone(), uid: Some(uid), gid: Some(gid), ..Default::default() };
+    if let Some(pathbuf) = pathbuf {
+        if denied(caps::Capability::Chown) {
+            return fail("lchown", args, libc::EPERM);
+        }
+        if update_metadata(pathbuf, |meta, real| meta.chown(uid, gid, real.is_dir())) {
+            trace("lchown", args, Some(0), None);
+            return 0;
+        }
+    }
+    
+    passthrough("lchown", args, real::lchown(path, uid, gid))
+}
+
+/// Intercept fchown system call, finding the file through /proc/self/fd
+#[no_mangle]
+pub unsafe extern "C" fn fchown(fd: c_int, uid: libc::uid_t, gid: libc::gid_t) -> c_int {
+    log::debug!("Intercepted fchown: fd={}, uid={}, gid={}", fd, uid, gid);
+    
+    let pathbuf = fd_path(fd);
+    let args = TraceArgs { path: pathbuf.clone(), fd: Some(fd), uid: Some(uid), gid: Some(gid), ..Default::default() };
+    if let Some(pathbuf) = pathbuf {
+        if denied(caps::Capability::Chown) {
+            return fail("fchown", args, libc::EPERM);
+        }
+        if update_metadata(pathbuf, |meta, real| meta.chown(uid, gid, real.is_dir())) {
+            trace("fchown", args, Some(0), None);
+            return 0;
+        }
+    }
+    
+    passthrough("fchown", args, real::fchown(fd, uid, gid))
+}
+
+/// Intercept fchownat, which coreutils chown and install call for every
+/// file, and which stands behind chown and lchown in some C libraries
+#[no_mangle]
+pub unsafe extern "C" fn fchownat(dirfd: c_int, path: *const c_char, uid: libc::uid_t, gid: libc::gid_t, flags: c_int) -> c_int {
+    log::debug!("Intercepted fchownat: uid={}, gid={}, flags={:#x}", uid, gid, flags);
+    
+    let pathbuf = at_to_pathbuf(dirfd, path, flags);
+    let args = TraceArgs { path: pathbuf.clone(), uid: Some(uid), gid: Some(gid), ..Default::default() };
+    if let Some(pathbuf) = pathbuf {
+        if denied(caps::Capability::Chown) {
+            return fail("fchownat", args, libc::EPERM);
+        }
+        if update_metadata(pathbuf, |meta, real| meta.chown(uid, gid, real.is_dir())) {
+            trace("fchownat", args, Some(0), None);
+            return 0;
+        }
+    }
+    
+    passthrough("fchownat", args, real::fchownat(dirfd, path, uid, gid, flags))
+}
+
+/// Intercept chmod system call
+#[no_mangle]
+pub unsafe extern "C" fn chmod(path: *const c_char, mode: libc::mode_t) -> c_int {
+    log::debug!("Intercepted chmod: mode={:o}", mode);
+    
+    let pathbuf = cstr_to_pathbuf(path, true);
+    let args = TraceArgs { path: pathbuf.clone(), mode: Some(mode), ..Default::default() };
+    if let Some(pathbuf) = pathbuf {
+        if denied(caps::Capability::Fowner) {
+            return fail("chmod", args, libc::EPERM);
+        }
+        if update_metadata(pathbuf, |meta, _| meta.mode = mode & 0o7777) {
+            trace("chmod", args, Some(0), None);
+            return 0;
+        }
+    }
+    
+    passthrough("chmod", args, real::chmod(path, mode))
+}
+
+/// Intercept fchmod system call, see fchown
+#[no_mangle]
+pub unsafe extern "C" fn fchmod(fd: c_int, mode: libc::mode_t) -> c_int {
+    log::debug!("Intercepted fchmod: fd={}, mode={:o}", fd, mode);
+    
+    let pathbuf = fd_path(fd);
+    let args = TraceArgs { path: pathbuf.clone(), fd: Some(fd), mode: Some(mode), ..Default::default() };
+    if let Some(pathbuf) = pathbuf {
+        if denied(caps::Capability::Fowner) {
+            return fail("fchmod", args, libc::EPERM);
+        }
+        if update_metadata(pathbuf, |meta, _| meta.mode = mode & 0o7777) {
+            trace("fchmod", args, Some(0), None);
+            return 0;
+        }
+    }
+    
+    passthrough("fchmod", args, real::fchmod(fd, mode))
+}
+
+/// Intercept lchmod, which changes the mode of a symlink itself
+#[no_mangle]
+pub unsafe extern "C" fn lchmod(path: *const c_char, mode: libc::mode_t) -> c_int {
+    let pathbuf = cstr_to_pathbuf(path, false);
+    let args = TraceArgs { path: pathbuf.clone(), mode: Some(mode), ..Default::default() };
+    if let Some(pathbuf) = pathbuf {
+        if denied(caps::Capability::Fowner) {
+            return fail("lchmod", args, libc::EPERM);
+        }
+        if update_metadata(pathbuf, |meta, _| meta.mode = mode & 0o7777) {
+            trace("lchmod", args, Some(0), None);
+            return 0;
+        }
+    }
+    
+    passthrough("lchmod", args, real::lchmod(path, mode))
+}
+
+/// Intercept fchmodat, which coreutils chmod calls for every file
+#[no_mangle]
+pub unsafe extern "C" fn fchmodat(dirfd: c_int, path: *const c_char, mode: libc::mode_t, flags: c_int) -> c_int {
+    log::debug!("Intercepted fchmodat: mode={:o}, flags={:#x}", mode, flags);
+    
+    let pathbuf = at_to_pathbuf(dirfd, path, flags);
+    let args = TraceArgs { path: pathbuf.clone(), mode: Some(mode), ..Default::default() };
+    if let Some(pathbuf) = pathbuf {
+        if denied(caps::Capability::Fowner) {
+            return fail("fchmodat", args, libc::EPERM);
+        }
+        if update_metadata(pathbuf, |meta, _| meta.mode = mode & 0o7777) {
+            trace("fchmodat", args, Some(0), None);
+            return 0;
+        }
+    }
+    
+    passthrough("fchmodat", args, real::fchmodat(dirfd, path, mode, flags))
+}
+
+/// Finish an access check. A permission failure becomes the answer root
+/// would get if the fake real, or with `effective` the fake effective, UID
+/// is 0; real errors such as ENOENT are left alone
+fn access_result(function: &str, ret: c_int, mode: c_int, effective: bool, path: impl FnOnce() -> Option<PathBuf>) -> c_int {
+    let errno = std::io::Error::last_os_error().raw_os_error().unwrap_or(0);
+    let args = TraceArgs { mode: Some(mode as u32), ..Default::default() };
+    if ret == 0 || errno != libc::EACCES {
+        return passthrough(function, args, ret);
+    }
+    let uid = with_credentials(|c| if effective { c.effective_uid } else { c.current_uid });
+    if uid != Some(0) {
+        return passthrough(function, args, ret);
+    }
+    
+    let granted = mode & libc::X_OK == 0
+        || match (path(), get_state_manager()) {
+            (Some(path), Some(manager)) => emulate::root_may_execute(&manager, &path),
+            _ => false,
+        };
+    if granted {
+        trace(function, args, Some(0), None);
+        return 0;
+    }
+    passthrough(function, args, ret)
+}
+
+/// Intercept access, which checks against the real UID
+#[no_mangle]
+pub unsafe extern "C" fn access(path: *const c_char, mode: c_int) -> c_int {
+    access_result("access", real::access(path, mode), mode, false, || cstr_to_pathbuf(path, true))
+}
+
+/// Intercept faccessat, which checks against the effective UID with
+/// AT_EACCESS
+#[no_mangle]
+pub unsafe extern "C" fn faccessat(dirfd: c_int, path: *const c_char, mode: c_int, flags: c_int) -> c_int {
+    let effective = flags & libc::AT_EACCESS != 0;
+    access_result("faccessat", real::faccessat(dirfd, path, mode, flags), mode, effective, || at_to_pathbuf(dirfd, path, flags))
+}
+
+/// Intercept euidaccess, which checks against the effective UID
+#[no_mangle]
+pub unsafe extern "C" fn euidaccess(path: *const c_char, mode: c_int) -> c_int {
+    access_result("euidaccess", real::euidaccess(path, mode), mode, true, || cstr_to_pathbuf(path, true))
+}
+
+/// Intercept eaccess, glibc's other name for euidaccess
+#[no_mangle]
+pub unsafe extern "C" fn eaccess(path: *const c_char, mode: c_int) -> c_int {
+    access_result("eaccess", real::eaccess(path, mode), mode, true, || cstr_to_pathbuf(path, true))
+}
+
+/// A stat structure the wrappers merge fake metadata into
+trait StatBuf {
+    fn merge(&mut self, meta: &FakeMetadata);
+}
+
+macro_rules! stat_buf {
+    ($($ty:ty),*) => {
+        $(
+            impl StatBuf for $ty {
+                /// Overlay fake ownership and permission bits, keeping the file type
+                fn merge(&mut self, meta: &FakeMetadata) {
+                    self.st_mode = (self.st_mode & libc::S_IFMT) | (meta.mode & 0o7777);
+                    self.st_uid = meta.uid;
+                    self.st_gid = meta.gid;
+                }
+            }
+        )*
+    };
+}
+
+stat_buf!(libc::stat);
+#[cfg(target_env = "gnu")]
+stat_buf!(libc::stat64);
+
+/// The fake metadata recorded for the file `path` resolves to. Nothing is
+/// looked up for the library's own calls
+fn fake_metadata(path: impl FnOnce() -> Option<PathBuf>) -> Option<(PathBuf, FakeMetadata)> {
+    if is_busy() {
+        return None;
+    }
+    busy(|| {
+        let path = path()?;
+        let manager = get_state_manager()?;
+        let meta = manager.get_state().lock().unwrap().get_metadata(&path).cloned()?;
+        Some((path, meta))
+    })
+}
+
+/// Finish a stat call, merging the fake metadata of its file into a
+/// successful result
+unsafe fn stat_result<T: StatBuf>(function: &str, ret: c_int, buf: *mut T, path: impl FnOnce() -> Option<PathBuf>) -> c_int {
+    if ret != 0 || buf.is_null() {
+        return ret;
+    }
+    if let Some((path, meta)) = fake_metadata(path) {
+        (*buf).merge(&meta);
+        trace(function, TraceArgs { path: Some(path), ..Default::default() }, Some(0), None);
+    }
+    ret
+}
+
+/// Intercept stat to report fake ownership and mode
+#[no_mangle]
+pub unsafe extern "C" fn stat(path: *const c_char, buf: *mut libc::stat) -> c_int {
+    stat_result("stat", real::stat(path, buf), buf, || cstr_to_pathbuf(path, true))
+}
+
+/// Intercept lstat, which reports on a symlink itself
+#[no_mangle]
+pub unsafe extern "C" fn lstat(path: *const c_char, buf: *mut libc::stat) -> c_int {
+    stat_result("lstat", real::lstat(path, buf), buf, || cstr_to_pathbuf(path, false))
+}
+
+/// Intercept fstat, finding the file through /proc/self/fd
+#[no_mangle]
+pub unsafe extern "C" fn fstat(fd: c_int, buf: *mut libc::stat) -> c_int {
+    stat_result("fstat", real::fstat(fd, buf), buf, || fd_path(fd))
+}
+
+/// Intercept fstatat, which coreutils use for nearly everything
+#[no_mangle]
+pub unsafe extern "C" fn fstatat(dirfd: c_int, path: *const c_char, buf: *mut libc::stat, flags: c_int) -> c_int {
+    stat_result("fstatat", real::fstatat(dirfd, path, buf, flags), buf, || at_to_pathbuf(dirfd, path, flags))
+}
+
+/// Intercept statx, which only fills in the fields its mask asks for.
+/// `struct statx` is the kernel's own, so the syscall layout applies
+#[no_mangle]
+pub unsafe extern "C" fn statx(dirfd: c_int, path: *const c_char, flags: c_int, mask: c_uint, buf: *mut c_void) -> c_int {
+    let ret = real::statx(dirfd, path, flags, mask, buf);
+    if ret != 0 || buf.is_null() {
+        return ret;
+    }
+    if let Some((path, meta)) = fake_metadata(|| at_to_pathbuf(dirfd, path, flags)) {
+        let buf = std::slice::from_raw_parts_mut(buf as *mut u8, stat::STATX.prefix_len());
+        stat::overlay(&stat::STATX, buf, &meta);
+        trace("statx", TraceArgs { path: Some(path), ..Default::default() }, Some(0), None);
+    }
+    ret
+}
+
+/// Intercept stat64, the large-file stat of 32-bit glibc programs
+#[cfg(target_env = "gnu")]
+#[no_mangle]
+pub unsafe extern "C" fn stat64(path: *const c_char, buf: *mut libc::stat64) -> c_int {
+    stat_result("stat64", real::stat64(path, buf), buf, || cstr_to_pathbuf(path, true))
+}
+
+/// Intercept lstat64, see stat64
+#[cfg(target_env = "gnu")]
+#[no_mangle]
+pub unsafe extern "C" fn lstat64(path: *const c_char, buf: *mut libc::stat64) -> c_int {
+    stat_result("lstat64", real::lstat64(path, buf), buf, || cstr_to_pathbuf(path, false))
+}
+
+/// Intercept fstat64, see stat64
+#[cfg(target_env = "gnu")]
+#[no_mangle]
+pub unsafe extern "C" fn fstat64(fd: c_int, buf: *mut libc::stat64) -> c_int {
+    stat_result("fstat64", real::fstat64(fd, buf), buf, || fd_path(fd))
+}
+
+/// Intercept fstatat64, see stat64
+#[cfg(target_env = "gnu")]
+#[no_mangle]
+pub unsafe extern "C" fn fstatat64(dirfd: c_int, path: *const c_char, buf: *mut libc::stat64, flags: c_int) -> c_int {
+    stat_result("fstatat64", real::fstatat64(dirfd, path, buf, flags), buf, || at_to_pathbuf(dirfd, path, flags))
+}
+
+/// Intercept __xstat, which programs built against glibc before 2.33
+/// call for stat
+#[cfg(target_env = "gnu")]
+#[no_mangle]
+pub unsafe extern "C" fn __xstat(version: c_int, path: *const c_char, buf: *mut libc::stat) -> c_int {
+    stat_result("stat", real::__xstat(version, path, buf), buf, || cstr_to_pathbuf(path, true))
+}
+
+/// Intercept __lxstat, the old lstat
+#[cfg(target_env = "gnu")]
+#[no_mangle]
+pub unsafe extern "C" fn __lxstat(version: c_int, path: *const c_char, buf: *mut libc::stat) -> c_int {
+    stat_result("lstat", real::__lxstat(version, path, buf), buf, || cstr_to_pathbuf(path, false))
+}
+
+/// Intercept __fxstat, the old fstat
+#[cfg(target_env = "gnu")]
+#[no_mangle]
+pub unsafe extern "C" fn __fxstat(version: c_int, fd: c_int, buf: *mut libc::stat) -> c_int {
+    stat_result("fstat", real::__fxstat(version, fd, buf), buf, || fd_path(fd))
+}
+
+/// Intercept __fxstatat, the old fstatat
+#[cfg(target_env = "gnu")]
+#[no_mangle]
+pub unsafe extern "C" fn __fxstatat(version: c_int, dirfd: c_int, path: *const c_char, buf: *mut libc::stat, flags: c_int) -> c_int {
+    stat_result("fstatat", real::__fxstatat(version, dirfd, path, buf, flags), buf, || at_to_pathbuf(dirfd, path, flags))
+}
+
+/// Intercept __xstat64, the old stat64
+#[cfg(target_env = "gnu")]
+#[no_mangle]
+pub unsafe extern "C" fn __xstat64(version: c_int, path: *const c_char, buf: *mut libc::stat64) -> c_int {
+    stat_result("stat64", real::__xstat64(version, path, buf), buf, || cstr_to_pathbuf(path, true))
+}
+
+/// Intercept __lxstat64, the old lstat64
+#[cfg(target_env = "gnu")]
+#[no_mangle]
+pub unsafe extern "C" fn __lxstat64(version: c_int, path: *const c_char, buf: *mut libc::stat64) -> c_int {
+    stat_result("lstat64", real::__lxstat64(version, path, buf), buf, || cstr_to_pathbuf(path, false))
+}
+
+/// Intercept __fxstat64, the old fstat64
+#[cfg(target_env = "gnu")]
+#[no_mangle]
+pub unsafe extern "C" fn __fxstat64(version: c_int, fd: c_int, buf: *mut libc::stat64) -> c_int {
+    stat_result("fstat64", real::__fxstat64(version, fd, buf), buf, || fd_path(fd))
+}
+
+/// Intercept __fxstatat64, the old fstatat64
+#[cfg(target_env = "gnu")]
+#[no_mangle]
+pub unsafe extern "C" fn __fxstatat64(version: c_int, dirfd: c_int, path: *const c_char, buf: *mut libc::stat64, flags: c_int) -> c_int {
+    stat_result("fstatat64", real::__fxstatat64(version, dirfd, path, buf, flags), buf, || at_to_pathbuf(dirfd, path, flags))
+}
+
+/// Intercept setuid system call
+#[no_mangle]
+pub unsafe extern "C" fn setuid(uid: libc::uid_t) -> c_int {
+    log::debug!("Intercepted setuid: uid={}", uid);
+    
+    let args = TraceArgs { uid: Some(uid), ..Default::default() };
+    if let Some(result) = with_credentials(|c| c.setuid(uid)) {
+        return credential_result("setuid", args, result);
+    }
+    
+    passthrough("setuid", args, real::setuid(uid))
+}
+
+/// Intercept setgid system call
+#[no_mangle]
+pub unsafe extern "C" fn setgid(gid: libc::gid_t) -> c_int {
+    log::debug!("Intercepted setgid: gid={}", gid);
+    
+    let args = TraceArgs { gid: Some(gid), ..Default::default() };
+    if let Some(result) = with_credentials(|c| c.setgid(gid)) {
+        return credential_result("setgid", args, result);
+    }
+    
+    passthrough("setgid", args, real::setgid(gid))
+}
+
+/// Intercept seteuid, which glibc implements with a direct setresuid
+/// syscall rather than through the setresuid symbol
+#[no_mangle]
+pub unsafe extern "C" fn seteuid(euid: libc::uid_t) -> c_int {
+    let args = TraceArgs { uid: Some(euid), ..Default::default() };
+    if let Some(result) = with_credentials(|c| c.setresuid(u32::MAX, euid, u32::MAX)) {
+        return credential_result("seteuid", args, result);
+    }
+    
+    passthrough("seteuid", args, real::seteuid(euid))
+}
+
+/// Intercept setegid, see seteuid
+#[no_mangle]
+pub unsafe extern "C" fn setegid(egid: libc::gid_t) -> c_int {
+    let args = TraceArgs { gid: Some(egid), ..Default::default() };
+    if let Some(result) = with_credentials(|c| c.setresgid(u32::MAX, egid, u32::MAX)) {
+        return credential_result("setegid", args, result);
+    }
+    
+    passthrough("setegid", args, real::setegid(egid))
+}
+
+/// Intercept setreuid system call
+#[no_mangle]
+pub unsafe extern "C" fn setreuid(ruid: libc::uid_t, euid: libc::uid_t) -> c_int {
+    log::debug!("Intercepted setreuid: ruid={}, euid={}", ruid, euid);
+    
+    let args = TraceArgs { uid: Some(euid), ..Default::default() };
+    if let Some(result) = with_credentials(|c| c.setreuid(ruid, euid)) {
+        return credential_result("setreuid", args, result);
+    }
+    
+    passthrough("setreuid", args, real::setreuid(ruid, euid))
+}
+
+/// Intercept setregid system call
+#[no_mangle]
+pub unsafe extern "C" fn setregid(rgid: libc::gid_t, egid: libc::gid_t) -> c_int {
+    log::debug!("Intercepted setregid: rgid={}, egid={}", rgid, egid);
+    
+    let args = TraceArgs { gid: Some(egid), ..Default::default() };
+    if let Some(result) = with_credentials(|c| c.setregid(rgid, egid)) {
+        return credential_result("setregid", args, result);
+    }
+    
+    passthrough("setregid", args, real::setregid(rgid, egid))
+}
+
+/// Intercept setresuid system call
+#[no_mangle]
+pub unsafe extern "C" fn setresuid(ruid: libc::uid_t, euid: libc::uid_t, suid: libc::uid_t) -> c_int {
+    log::debug!("Intercepted setresuid: ruid={}, euid={}, suid={}", ruid, euid, suid);
+    
+    let args = TraceArgs { uid: Some(euid), ..Default::default() };
+    if let Some(result) = with_credentials(|c| c.setresuid(ruid, euid, suid)) {
+        return credential_result("setresuid", args, result);
+    }
+    
+    passthrough("setresuid", args, real::setresuid(ruid, euid, suid))
+}
+
+/// Intercept setresgid system call
+#[no_mangle]
+pub unsafe extern "C" fn setresgid(rgid: libc::gid_t, egid: libc::gid_t, sgid: libc::gid_t) -> c_int {
+    log::debug!("Intercepted setresgid: rgid={}, egid={}, sgid={}", rgid, egid, sgid);
+    
+    let args = TraceArgs { gid: Some(egid), ..Default::default() };
+    if let Some(result) = with_credentials(|c| c.setresgid(rgid, egid, sgid)) {
+        return credential_result("setresgid", args, result);
+    }
+    
+    passthrough("setresgid", args, real::setresgid(rgid, egid, sgid))
+}
+
+/// Intercept setfsuid, which returns the previous filesystem UID
+#[no_mangle]
+pub unsafe extern "C" fn setfsuid(uid: libc::uid_t) -> c_int {
+    let args = TraceArgs { uid: Some(uid), ..Default::default() };
+    if let Some(old) = with_credentials(|c| c.setfsuid(uid)) {
+        trace("setfsuid", args, Some(old as i64), None);
+        return old as c_int;
+    }
+    
+    passthrough("setfsuid", args, real::setfsuid(uid))
+}
+
+/// Intercept setfsgid, which returns the previous filesystem GID
+#[no_mangle]
+pub unsafe extern "C" fn setfsgid(gid: libc::gid_t) -> c_int {
+    let args = TraceArgs { gid: Some(gid), ..Default::default() };
+    if let Some(old) = with_credentials(|c| c.setfsgid(gid)) {
+        trace("setfsgid", args, Some(old as i64), None);
+        return old as c_int;
+    }
+    
+    passthrough("setfsgid", args, real::setfsgid(gid))
+}
+
+/// Intercept getresuid to report the fake real, effective and saved UIDs
+#[no_mangle]
+pub unsafe extern "C" fn getresuid(ruid: *mut libc::uid_t, euid: *mut libc::uid_t, suid: *mut libc::uid_t) -> c_int {
+    if ruid.is_null() || euid.is_null() || suid.is_null() {
+        return fail("getresuid", TraceArgs::default(), libc::EFAULT);
+    }
+    if let Some(ids) = with_credentials(|c| (c.current_uid, c.effective_uid, c.saved_uid)) {
+        (*ruid, *euid, *suid) = ids;
+        trace("getresuid", TraceArgs::default(), Some(0), None);
+        return 0;
+    }
+    
+    passthrough("getresuid", TraceArgs::default(), real::getresuid(ruid, euid, suid))
+}
+
+/// Intercept getresgid to report the fake real, effective and saved GIDs
+#[no_mangle]
+pub unsafe extern "C" fn getresgid(rgid: *mut libc::gid_t, egid: *mut libc::gid_t, sgid: *mut libc::gid_t) -> c_int {
+    if rgid.is_null() || egid.is_null() || sgid.is_null() {
+        return fail("getresgid", TraceArgs::default(), libc::EFAULT);
+    }
+    if let Some(ids) = with_credentials(|c| (c.current_gid, c.effective_gid, c.saved_gid)) {
+        (*rgid, *egid, *sgid) = ids;
+        trace("getresgid", TraceArgs::default(), Some(0), None);
+        return 0;
+    }
+    
+    passthrough("getresgid", TraceArgs::default(), real::getresgid(rgid, egid, sgid))
+}
+
+/// Intercept getgroups to report the fake supplementary groups
+#[no_mangle]
+pub unsafe extern "C" fn getgroups(size: c_int, list: *mut libc::gid_t) -> c_int {
+    if let Some(groups) = with_credentials(|c| c.groups.clone()) {
+        if size < 0 || (size > 0 && (size as usize) < groups.len()) {
+            return fail("getgroups", TraceArgs::default(), libc::EINVAL);
+        }
+        if size > 0 && !groups.is_empty() {
+            // The kernel faults on the copy, not on a null list it never touches
+            if list.is_null() {
+                return fail("getgroups", TraceArgs::default(), libc::EFAULT);
+            }
+            std::ptr::copy_nonoverlapping(groups.as_ptr(), list, groups.len());
+        }
+        trace("getgroups", TraceArgs::default(), Some(groups.len() as i64), None);
+        return groups.len() as c_int;
+    }
+    
+    passthrough("getgroups", TraceArgs::default(), real::getgroups(size, list))
+}
+
+/// Intercept setgroups to replace the fake supplementary groups
+#[no_mangle]
+pub unsafe extern "C" fn setgroups(size: libc::size_t, list: *const libc::gid_t) -> c_int {
+    log::debug!("Intercepted setgroups: size={}", size);
+    
+    if size > 0 && list.is_null() {
+        return fail("setgroups", TraceArgs::default(), libc::EFAULT);
+    }
+    let groups = if size > 0 { std::slice::from_raw_parts(list, size).to_vec() } else { Vec::new() };
+    if let Some(result) = with_credentials(|c| c.setgroups(groups)) {
+        return credential_result("setgroups", TraceArgs::default(), result);
+    }
+    
+    passthrough("setgroups", TraceArgs::default(), real::setgroups(size, list))
+}
+
+/// Intercept initgroups, which glibc implements without going through the
+/// setgroups symbol: look the user's groups up and set them as fake ones
+#[no_mangle]
+pub unsafe extern "C" fn initgroups(user: *const c_char, group: libc::gid_t) -> c_int {
+    let args = TraceArgs { gid: Some(group), ..Default::default() };
+    if user.is_null() {
+        return fail("initgroups", args, libc::EFAULT);
+    }
+    // Our own getgrouplist rather than libc's, so that a user of the fake
+    // database gets the groups it lists
+    let mut groups: Vec<libc::gid_t> = vec![0; 64];
+    loop {
+        let mut count = groups.len() as c_int;
+        if getgrouplist(user, group, groups.as_mut_ptr(), &mut count) >= 0 {
+            groups.truncate(count as usize);
+            break;
+        }
+        // The list did not fit; `count` now holds the size it needs
+        groups.resize((count as usize).max(groups.len() * 2), 0);
+    }
+    if let Some(result) = with_credentials(|c| c.setgroups(groups)) {
+        return credential_result("initgroups", args, result);
+    }
+    
+    passthrough("initgroups", args, real::initgroups(user, group))
+}
+
+/// Validate a capget/capset header the way the kernel does, giving the
+/// number of words per set and the thread the call is about. An unknown
+/// version gets the preferred one written back, for libcap to probe
+unsafe fn cap_header(header: *mut u32) -> std::result::Result<(usize, i32), c_int> {
+    if header.is_null() {
+        return Err(libc::EFAULT);
+    }
+    match caps::version_words(*header) {
+        Some(words) => Ok((words, *header.add(1) as i32)),
+        None => {
+            *header = caps::VERSION_3;
+            Err(libc::EINVAL)
+        }
+    }
+}
+
+/// Whether a capget/capset pid names this thread
+fn is_self(pid: i32) -> bool {
+    pid == 0 || pid == unsafe { libc::gettid() }
+}
+
+/// Intercept capget to report the fake capability sets
+#[no_mangle]
+pub unsafe extern "C" fn capget(header: *mut u32, data: *mut u32) -> c_int {
+    let (words, pid) = match cap_header(header) {
+        Ok(header) => header,
+        Err(errno) => return fail("capget", TraceArgs::default(), errno),
+    };
+    if is_self(pid) {
+        if let Some(sets) = with_credentials(|c| c.capabilities) {
+            // A null data pointer only asks whether the version is supported
+            if !data.is_null() {
+                let words = sets.user_data(words);
+                std::ptr::copy_nonoverlapping(words.as_ptr(), data, words.len());
+            }
+            trace("capget", TraceArgs::default(), Some(0), None);
+            return 0;
+        }
+    }
+    
+    passthrough("capget", TraceArgs::default(), real::capget(header, data))
+}
+
+/// Intercept capset to change the fake capability sets
+#[no_mangle]
+pub unsafe extern "C" fn capset(header: *mut u32, data: *const u32) -> c_int {
+    let (words, pid) = match cap_header(header) {
+        Ok(header) => header,
+        Err(errno) => return fail("capset", TraceArgs::default(), errno),
+    };
+    if !is_self(pid) {
+        return fail("capset", TraceArgs::default(), libc::EPERM);
+    }
+    if data.is_null() {
+        return fail("capset", TraceArgs::default(), libc::EFAULT);
+    }
+    let data = std::slice::from_raw_parts(data, words * 3);
+    if let Some(result) = with_credentials(|c| c.capabilities.capset(data)) {
+        return credential_result("capset", TraceArgs::default(), result);
+    }
+    
+    passthrough("capset", TraceArgs::default(), real::capset(header, data.as_ptr()))
+}
+
+/// Intercept prctl for its capability operations. prctl is variadic, but
+/// the unused arguments are passed in registers all the same, so they can
+/// be taken as fixed ones
+#[no_mangle]
+pub unsafe extern "C" fn prctl(option: c_int, arg2: c_ulong, arg3: c_ulong, arg4: c_ulong, arg5: c_ulong) -> c_int {
+    let args = [arg2, arg3, arg4, arg5].map(u64::from);
+    let trace_args = TraceArgs { option: Some(option), arg: Some(args[0]), ..Default::default() };
+    match with_credentials(|c| c.prctl(option, args)).flatten() {
+        Some(Ok(ret)) => {
+            trace("prctl", trace_args, Some(ret), None);
+            ret as c_int
+        }
+        Some(Err(errno)) => fail("prctl", trace_args, errno as c_int),
+        None => real::prctl(option, arg2, arg3, arg4, arg5),
+    }
+}
+
+/// Intercept geteuid to return fake root
+#[no_mangle]
+pub unsafe extern "C" fn geteuid() -> libc::uid_t {
+    if let Some(id) = with_credentials(|c| c.effective_uid) {
+        trace("geteuid", TraceArgs::default(), Some(id as i64), None);
+        return id;
+    }
+    
+    real::geteuid()
+}
+
+/// Intercept getuid to return fake root
+#[no_mangle]
+pub unsafe extern "C" fn getuid() -> libc::uid_t {
+    if let Some(id) = with_credentials(|c| c.current_uid) {
+        trace("getuid", TraceArgs::default(), Some(id as i64), None);
+        return id;
+    }
+    
+    real::getuid()
+}
+
+/// Intercept getegid to return fake root group
+#[no_mangle]
+pub unsafe extern "C" fn getegid() -> libc::gid_t {
+    if let Some(id) = with_credentials(|c| c.effective_gid) {
+        trace("getegid", TraceArgs::default(), Some(id as i64), None);
+        return id;
+    }
+    
+    real::getegid()
+}
+
+/// Intercept getgid to return fake root group
+#[no_mangle]
+pub unsafe extern "C" fn getgid() -> libc::gid_t {
+    if let Some(id) = with_credentials(|c| c.current_gid) {
+        trace("getgid", TraceArgs::default(), Some(id as i64), None);
+        return id;
+    }
+    
+    real::getgid()
+}
+
+/// Lays strings and pointer arrays out in the caller's buffer of a *_r
+/// lookup, failing once it is full
+struct Arena {
+    start: *mut u8,
+    len: usize,
+    used: usize,
+}
+
+impl Arena {
+    fn new(start: *mut c_char, len: usize) -> Self {
+        Self { start: start as *mut u8, len, used: 0 }
+    }
+    
+    /// Claim `size` bytes aligned for `align`
+    fn claim(&mut self, size: usize, align: usize) -> Option<*mut u8> {
+        let offset = (self.start as usize + self.used).next_multiple_of(align) - self.start as usize;
+        let end = offset.checked_add(size)?;
+        if self.start.is_null() || end > self.len {
+            return None;
+        }
+        self.used = end;
+        Some(unsafe { self.start.add(offset) })
+    }
+    
+    fn string(&mut self, s: &str) -> Option<*mut c_char> {
+        let out = self.claim(s.len() + 1, 1)?;
+        unsafe {
+            std::ptr::copy_nonoverlapping(s.as_ptr(), out, s.len());
+            *out.add(s.len()) = 0;
+        }
+        Some(out as *mut c_char)
+    }
+    
+    /// A NULL-terminated array of copies of `strings`
+    fn strings(&mut self, strings: &[String]) -> Option<*mut *mut c_char> {
+        let copies = strings.iter().map(|s| self.string(s)).collect::<Option<Vec<_>>>()?;
+        let size = std::mem::size_of::<*mut c_char>();
+        let out = self.claim((copies.len() + 1) * size, size)? as *mut *mut c_char;
+        for (i, copy) in copies.into_iter().chain([std::ptr::null_mut()]).enumerate() {
+            unsafe { *out.add(i) = copy };
+        }
+        Some(out)
+    }
+}
+
+unsafe fn fill_passwd(user: &User, pwd: *mut libc::passwd, arena: &mut Arena) -> Option<()> {
+    (*pwd).pw_name = arena.string(&user.name)?;
+    (*pwd).pw_passwd = arena.string(&user.passwd)?;
+    (*pwd).pw_uid = user.uid;
+    (*pwd).pw_gid = user.gid;
+    #[cfg(not(all(target_os = "android", target_pointer_width = "32")))]
+    {
+        (*pwd).pw_gecos = arena.string(&user.gecos)?;
+    }
+    (*pwd).pw_dir = arena.string(&user.dir)?;
+    (*pwd).pw_shell = arena.string(&user.shell)?;
+    Some(())
+}
+
+unsafe fn fill_group(group: &Group, grp: *mut libc::group, arena: &mut Arena) -> Option<()> {
+    (*grp).gr_name = arena.string(&group.name)?;
+    (*grp).gr_passwd = arena.string(&group.passwd)?;
+    (*grp).gr_gid = group.gid;
+    (*grp).gr_mem = arena.strings(&group.members)?;
+    Some(())
+}
+
+/// Finish a *_r lookup of a fake entry, which reports ERANGE rather than
+/// setting errno when the caller's buffer is too small
+unsafe fn entry_r<T>(
+    entry: *mut T,
+    buf: *mut c_char,
+    len: libc::size_t,
+    result: *mut *mut T,
+    fill: impl FnOnce(*mut T, &mut Arena) -> Option<()>,
+) -> c_int {
+    *result = std::ptr::null_mut();
+    if fill(entry, &mut Arena::new(buf, len)).is_none() {
+        return libc::ERANGE;
+    }
+    *result = entry;
+    0
+}
+
+thread_local! {
+    /// What getpwnam and friends return a pointer to, valid until this
+    /// thread's next lookup like the C library's static buffer
+    static PASSWD: RefCell<(libc::passwd, Vec<u8>)> = const { RefCell::new((unsafe { std::mem::zeroed() }, Vec::new())) };
+    
+    /// The same for getgrnam and friends
+    static GROUP: RefCell<(libc::group, Vec<u8>)> = const { RefCell::new((unsafe { std::mem::zeroed() }, Vec::new())) };
+    
+    /// The name getlogin returns
+    static LOGIN: RefCell<CString> = RefCell::new(CString::default());
+}
+
+/// Fill in one of the thread's static entries, growing its buffer until
+/// the entry fits
+fn static_entry<T>(storage: &'static LocalKey<RefCell<(T, Vec<u8>)>>, fill: impl Fn(*mut T, &mut Arena) -> Option<()>) -> *mut T {
+    storage
+        .try_with(|storage| {
+            let (entry, buf) = &mut *storage.borrow_mut();
+            if buf.is_empty() {
+                buf.resize(256, 0);
+            }
+            while fill(entry, &mut Arena::new(buf.as_mut_ptr() as *mut c_char, buf.len())).is_none() {
+                buf.resize(buf.len() * 2, 0);
+            }
+            entry as *mut T
+        })
+        .unwrap_or(std::ptr::null_mut())
+}
+
+fn static_passwd(user: &User) -> *mut libc::passwd {
+    static_entry(&PASSWD, |pwd, arena| unsafe { fill_passwd(user, pwd, arena) })
+}
+
+fn static_group(group: &Group) -> *mut libc::group {
+    static_entry(&GROUP, |grp, arena| unsafe { fill_group(group, grp, arena) })
+}
+
+/// The session's fake user database, if it has one
+fn user_db() -> Option<&'static UserDb> {
+    USER_DB.as_ref()
+}
+
+/// A name argument, which cannot match a fake entry unless it is UTF-8
+unsafe fn cstr_name<'a>(name: *const c_char) -> Option<&'a str> {
+    if name.is_null() {
+        return None;
+    }
+    CStr::from_ptr(name).to_str().ok()
+}
+
+/// Intercept getpwuid to look users up in the fake database first
+#[no_mangle]
+pub unsafe extern "C" fn getpwuid(uid: libc::uid_t) -> *mut libc::passwd {
+    match user_db().and_then(|db| db.user_by_uid(uid)) {
+        Some(user) => static_passwd(user),
+        None => real::getpwuid(uid),
+    }
+}
+
+/// Intercept getpwnam, see getpwuid
+#[no_mangle]
+pub unsafe extern "C" fn getpwnam(name: *const c_char) -> *mut libc::passwd {
+    match user_db().zip(cstr_name(name)).and_then(|(db, name)| db.user_by_name(name)) {
+        Some(user) => static_passwd(user),
+        None => real::getpwnam(name),
+    }
+}
+
+/// Intercept getpwuid_r, the reentrant getpwuid
+#[no_mangle]
+pub unsafe extern "C" fn getpwuid_r(
+    uid: libc::uid_t,
+    pwd: *mut libc::passwd,
+    buf: *mut c_char,
+    len: libc::size_t,
+    result: *mut *mut libc::passwd,
+) -> c_int {
+    match user_db().and_then(|db| db.user_by_uid(uid)) {
+        Some(user) => entry_r(pwd, buf, len, result, |pwd, arena| fill_passwd(user, pwd, arena)),
+        None => real::getpwuid_r(uid, pwd, buf, len, result),
+    }
+}
+
+/// Intercept getpwnam_r, the reentrant getpwnam
+#[no_mangle]
+pub unsafe extern "C" fn getpwnam_r(
+    name: *const c_char,
+    pwd: *mut libc::passwd,
+    buf: *mut c_char,
+    len: libc::size_t,
+    result: *mut *mut libc::passwd,
+) -> c_int {
+    match user_db().zip(cstr_name(name)).and_then(|(db, name)| db.user_by_name(name)) {
+        Some(user) => entry_r(pwd, buf, len, result, |pwd, arena| fill_passwd(user, pwd, arena)),
+        None => real::getpwnam_r(name, pwd, buf, len, result),
+    }
+}
+
+/// Position of getpwent and getgrent in the fake database
+static PASSWD_CURSOR: AtomicUsize = AtomicUsize::new(0);
+static GROUP_CURSOR: AtomicUsize = AtomicUsize::new(0);
+
+/// Intercept getpwent to walk the fake database instead of the host's
+#[no_mangle]
+pub unsafe extern "C" fn getpwent() -> *mut libc::passwd {
+    match user_db() {
+        Some(db) => match db.users.get(PASSWD_CURSOR.fetch_add(1, Ordering::Relaxed)) {
+            Some(user) => static_passwd(user),
+            None => std::ptr::null_mut(),
+        },
+        None => real::getpwent(),
+    }
+}
+
+/// Intercept setpwent to rewind getpwent
+#[no_mangle]
+pub unsafe extern "C" fn setpwent() {
+    match user_db() {
+        Some(_) => PASSWD_CURSOR.store(0, Ordering::Relaxed),
+        None => real::setpwent(),
+    }
+}
+
+/// Intercept endpwent, which also rewinds getpwent
+#[no_mangle]
+pub unsafe extern "C" fn endpwent() {
+    match user_db() {
+        Some(_) => PASSWD_CURSOR.store(0, Ordering::Relaxed),
+        None => real::endpwent(),
+    }
+}
+
+/// Intercept getgrgid to look groups up in the fake database first
+#[no_mangle]
+pub unsafe extern "C" fn getgrgid(gid: libc::gid_t) -> *mut libc::group {
+    match user_db().and_then(|db| db.group_by_gid(gid)) {
+        Some(group) => static_group(group),
+        None => real::getgrgid(gid),
+    }
+}
+
+/// Intercept getgrnam, see getgrgid
+#[no_mangle]
+pub unsafe extern "C" fn getgrnam(name: *const c_char) -> *mut libc::group {
+    match user_db().zip(cstr_name(name)).and_then(|(db, name)| db.group_by_name(name)) {
+        Some(group) => static_group(group),
+        None => real::getgrnam(name),
+    }
+}
+
+/// Intercept getgrgid_r, the reentrant getgrgid
+#[no_mangle]
+pub unsafe extern "C" fn getgrgid_r(
+    gid: libc::gid_t,
+    grp: *mut libc::group,
+    buf: *mut c_char,
+    len: libc::size_t,
+    result: *mut *mut libc::group,
+) -> c_int {
+    match user_db().and_then(|db| db.group_by_gid(gid)) {
+        Some(group) => entry_r(grp, buf, len, result, |grp, arena| fill_group(group, grp, arena)),
+        None => real::getgrgid_r(gid, grp, buf, len, result),
+    }
+}
+
+/// Intercept getgrnam_r, the reentrant getgrnam
+#[no_mangle]
+pub unsafe extern "C" fn getgrnam_r(
+    name: *const c_char,
+    grp: *mut libc::group,
+    buf: *mut c_char,
+    len: libc::size_t,
+    result: *mut *mut libc::group,
+) -> c_int {
+    match user_db().zip(cstr_name(name)).and_then(|(db, name)| db.group_by_name(name)) {
+        Some(group) => entry_r(grp, buf, len, result, |grp, arena| fill_group(group, grp, arena)),
+        None => real::getgrnam_r(name, grp, buf, len, result),
+    }
+}
+
+/// Intercept getgrent to walk the fake database instead of the host's
+#[no_mangle]
+pub unsafe extern "C" fn getgrent() -> *mut libc::group {
+    match user_db() {
+        Some(db) => match db.groups.get(GROUP_CURSOR.fetch_add(1, Ordering::Relaxed)) {
+            Some(group) => static_group(group),
+            None => std::ptr::null_mut(),
+        },
+        None => real::getgrent(),
+    }
+}
+
+/// Intercept setgrent to rewind getgrent
+#[no_mangle]
+pub unsafe extern "C" fn setgrent() {
+    match user_db() {
+        Some(_) => GROUP_CURSOR.store(0, Ordering::Relaxed),
+        None => real::setgrent(),
+    }
+}
+
+/// Intercept endgrent, which also rewinds getgrent
+#[no_mangle]
+pub unsafe extern "C" fn endgrent() {
+    match user_db() {
+        Some(_) => GROUP_CURSOR.store(0, Ordering::Relaxed),
+        None => real::endgrent(),
+    }
+}
+
+/// Intercept getgrouplist to take the groups of a fake user from the fake
+/// database. What fits is stored either way, and `count` is set to the
+/// number of groups
+#[no_mangle]
+pub unsafe extern "C" fn getgrouplist(user: *const c_char, group: libc::gid_t, groups: *mut libc::gid_t, count: *mut c_int) -> c_int {
+    let name = match user_db().zip(cstr_name(user)) {
+        Some((db, name)) if db.user_by_name(name).is_some() => name,
+        _ => return real::getgrouplist(user, group, groups, count),
+    };
+    if count.is_null() {
+        real::set_errno(libc::EFAULT);
+        return -1;
+    }
+    
+    let list = user_db().map(|db| db.group_list(name, group)).unwrap_or_default();
+    let fits = list.len().min((*count).max(0) as usize);
+    if fits > 0 && !groups.is_null() {
+        std::ptr::copy_nonoverlapping(list.as_ptr(), groups, fits);
+    }
+    *count = list.len() as c_int;
+    if fits < list.len() {
+        -1
+    } else {
+        list.len() as c_int
+    }
+}
+
+/// Intercept getlogin to name the fake real UID instead of the user who
+/// logged in
+#[no_mangle]
+pub unsafe extern "C" fn getlogin() -> *mut c_char {
+    let pwd = match with_credentials(|c| c.current_uid) {
+        Some(uid) => getpwuid(uid),
+        None => std::ptr::null_mut(),
+    };
+    if pwd.is_null() {
+        return real::getlogin();
+    }
+    let name = CStr::from_ptr((*pwd).pw_name).to_owned();
+    LOGIN
+        .try_with(|login| {
+            *login.borrow_mut() = name;
+            login.borrow().as_ptr() as *mut c_char
+        })
+        .unwrap_or(std::ptr::null_mut())
+}
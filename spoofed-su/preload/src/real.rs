@@ -0,0 +1,196 @@
+use std::ffi::{c_void, CStr};
+use std::os::raw::{c_char, c_int, c_uint, c_ulong};
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+// Bionic uses other handle values on 32-bit targets than glibc and musl,
+// and the libc crate does not define RTLD_NEXT for Android at all
+#[cfg(all(target_os = "android", target_pointer_width = "32"))]
+const RTLD_NEXT: *mut c_void = 0xffff_fffe_usize as *mut c_void;
+#[cfg(not(all(target_os = "android", target_pointer_width = "32")))]
+const RTLD_NEXT: *mut c_void = -1isize as *mut c_void;
+
+/// Marks a symbol that was looked up and not found
+const MISSING: *mut c_void = usize::MAX as *mut c_void;
+
+/// A libc function as found past the wrappers this crate exports, looked up
+/// once on first use
+pub(crate) struct RealSymbol {
+    name: &'static str,
+    address: AtomicPtr<c_void>,
+}
+
+impl RealSymbol {
+    /// `name` is NUL-terminated
+    pub(crate) const fn new(name: &'static str) -> Self {
+        Self { name, address: AtomicPtr::new(std::ptr::null_mut()) }
+    }
+    
+    /// The address of the next definition after the calling object, which
+    /// for a preloaded library is the C library's own. Racing threads may
+    /// both look it up, but they find the same address
+    pub(crate) fn address(&self) -> Option<*mut c_void> {
+        let mut address = self.address.load(Ordering::Acquire);
+        if address.is_null() {
+            address = self.lookup().unwrap_or(MISSING);
+            self.address.store(address, Ordering::Release);
+        }
+        (address != MISSING).then_some(address)
+    }
+    
+    fn lookup(&self) -> Option<*mut c_void> {
+        let name = CStr::from_bytes_with_nul(self.name.as_bytes()).ok()?;
+        let address = unsafe { libc::dlsym(RTLD_NEXT, name.as_ptr()) };
+        (!address.is_null()).then_some(address)
+    }
+}
+
+/// Set the calling thread's errno
+pub(crate) fn set_errno(errno: c_int) {
+    #[cfg(target_os = "android")]
+    unsafe { *libc::__errno() = errno };
+    #[cfg(not(target_os = "android"))]
+    unsafe { *libc::__errno_location() = errno };
+}
+
+/// What a function the C library lacks returns
+trait Missing {
+    fn missing() -> Self;
+}
+
+impl Missing for i32 {
+    fn missing() -> Self {
+        -1
+    }
+}
+
+/// An id of -1, as the id getters cannot fail
+impl Missing for u32 {
+    fn missing() -> Self {
+        u32::MAX
+    }
+}
+
+impl<T> Missing for *mut T {
+    fn missing() -> Self {
+        std::ptr::null_mut()
+    }
+}
+
+impl Missing for () {
+    fn missing() -> Self {}
+}
+
+/// Fail a call to a function the C library lacks with ENOSYS
+fn missing<T: Missing>(name: &str) -> T {
+    log::warn!("{} not found in the C library", name);
+    set_errno(libc::ENOSYS);
+    T::missing()
+}
+
+/// Declare functions that call the genuine libc function of the same name
+macro_rules! real_functions {
+    ($($(#[$attr:meta])* fn $name:ident($($arg:ident: $ty:ty),*) -> $ret:ty;)*) => {
+        $(
+            $(#[$attr])*
+            pub(crate) unsafe fn $name($($arg: $ty),*) -> $ret {
+                static SYMBOL: RealSymbol = RealSymbol::new(concat!(stringify!($name), "\0"));
+                match SYMBOL.address() {
+                    Some(address) => {
+                        let function: unsafe extern "C" fn($($ty),*) -> $ret = std::mem::transmute(address);
+                        function($($arg),*)
+                    }
+                    None => missing(stringify!($name)),
+                }
+            }
+        )*
+    };
+}
+
+real_functions! {
+    fn chown(path: *const c_char, uid: libc::uid_t, gid: libc::gid_t) -> c_int;
+    fn lchown(path: *const c_char, uid: libc::uid_t, gid: libc::gid_t) -> c_int;
+    fn fchown(fd: c_int, uid: libc::uid_t, gid: libc::gid_t) -> c_int;
+    fn chmod(path: *const c_char, mode: libc::mode_t) -> c_int;
+    fn fchmod(fd: c_int, mode: libc::mode_t) -> c_int;
+    fn fchownat(dirfd: c_int, path: *const c_char, uid: libc::uid_t, gid: libc::gid_t, flags: c_int) -> c_int;
+    fn fchmodat(dirfd: c_int, path: *const c_char, mode: libc::mode_t, flags: c_int) -> c_int;
+    fn lchmod(path: *const c_char, mode: libc::mode_t) -> c_int;
+    fn access(path: *const c_char, mode: c_int) -> c_int;
+    fn faccessat(dirfd: c_int, path: *const c_char, mode: c_int, flags: c_int) -> c_int;
+    fn euidaccess(path: *const c_char, mode: c_int) -> c_int;
+    fn eaccess(path: *const c_char, mode: c_int) -> c_int;
+    
+    fn stat(path: *const c_char, buf: *mut libc::stat) -> c_int;
+    fn lstat(path: *const c_char, buf: *mut libc::stat) -> c_int;
+    fn fstat(fd: c_int, buf: *mut libc::stat) -> c_int;
+    fn fstatat(dirfd: c_int, path: *const c_char, buf: *mut libc::stat, flags: c_int) -> c_int;
+    fn statx(dirfd: c_int, path: *const c_char, flags: c_int, mask: c_uint, buf: *mut c_void) -> c_int;
+    #[cfg(target_env = "gnu")]
+    fn stat64(path: *const c_char, buf: *mut libc::stat64) -> c_int;
+    #[cfg(target_env = "gnu")]
+    fn lstat64(path: *const c_char, buf: *mut libc::stat64) -> c_int;
+    #[cfg(target_env = "gnu")]
+    fn fstat64(fd: c_int, buf: *mut libc::stat64) -> c_int;
+    #[cfg(target_env = "gnu")]
+    fn fstatat64(dirfd: c_int, path: *const c_char, buf: *mut libc::stat64, flags: c_int) -> c_int;
+    // The versioned entry points of glibc before 2.33, still called by
+    // programs built against it
+    #[cfg(target_env = "gnu")]
+    fn __xstat(version: c_int, path: *const c_char, buf: *mut libc::stat) -> c_int;
+    #[cfg(target_env = "gnu")]
+    fn __lxstat(version: c_int, path: *const c_char, buf: *mut libc::stat) -> c_int;
+    #[cfg(target_env = "gnu")]
+    fn __fxstat(version: c_int, fd: c_int, buf: *mut libc::stat) -> c_int;
+    #[cfg(target_env = "gnu")]
+    fn __fxstatat(version: c_int, dirfd: c_int, path: *const c_char, buf: *mut libc::stat, flags: c_int) -> c_int;
+    #[cfg(target_env = "gnu")]
+    fn __xstat64(version: c_int, path: *const c_char, buf: *mut libc::stat64) -> c_int;
+    #[cfg(target_env = "gnu")]
+    fn __lxstat64(version: c_int, path: *const c_char, buf: *mut libc::stat64) -> c_int;
+    #[cfg(target_env = "gnu")]
+    fn __fxstat64(version: c_int, fd: c_int, buf: *mut libc::stat64) -> c_int;
+    #[cfg(target_env = "gnu")]
+    fn __fxstatat64(version: c_int, dirfd: c_int, path: *const c_char, buf: *mut libc::stat64, flags: c_int) -> c_int;
+    
+    fn setuid(uid: libc::uid_t) -> c_int;
+    fn setgid(gid: libc::gid_t) -> c_int;
+    fn seteuid(euid: libc::uid_t) -> c_int;
+    fn setegid(egid: libc::gid_t) -> c_int;
+    fn setreuid(ruid: libc::uid_t, euid: libc::uid_t) -> c_int;
+    fn setregid(rgid: libc::gid_t, egid: libc::gid_t) -> c_int;
+    fn setresuid(ruid: libc::uid_t, euid: libc::uid_t, suid: libc::uid_t) -> c_int;
+    fn setresgid(rgid: libc::gid_t, egid: libc::gid_t, sgid: libc::gid_t) -> c_int;
+    fn setfsuid(uid: libc::uid_t) -> c_int;
+    fn setfsgid(gid: libc::gid_t) -> c_int;
+    fn getresuid(ruid: *mut libc::uid_t, euid: *mut libc::uid_t, suid: *mut libc::uid_t) -> c_int;
+    fn getresgid(rgid: *mut libc::gid_t, egid: *mut libc::gid_t, sgid: *mut libc::gid_t) -> c_int;
+    fn getgroups(size: c_int, list: *mut libc::gid_t) -> c_int;
+    fn setgroups(size: libc::size_t, list: *const libc::gid_t) -> c_int;
+    fn initgroups(user: *const c_char, group: libc::gid_t) -> c_int;
+    fn getuid() -> libc::uid_t;
+    fn geteuid() -> libc::uid_t;
+    fn getgid() -> libc::gid_t;
+    fn getegid() -> libc::gid_t;
+    
+    fn capget(header: *mut u32, data: *mut u32) -> c_int;
+    fn capset(header: *mut u32, data: *const u32) -> c_int;
+    // Variadic in C, but its arguments are passed like fixed ones
+    fn prctl(option: c_int, arg2: c_ulong, arg3: c_ulong, arg4: c_ulong, arg5: c_ulong) -> c_int;
+    
+    fn getpwuid(uid: libc::uid_t) -> *mut libc::passwd;
+    fn getpwnam(name: *const c_char) -> *mut libc::passwd;
+    fn getpwuid_r(uid: libc::uid_t, pwd: *mut libc::passwd, buf: *mut c_char, len: libc::size_t, result: *mut *mut libc::passwd) -> c_int;
+    fn getpwnam_r(name: *const c_char, pwd: *mut libc::passwd, buf: *mut c_char, len: libc::size_t, result: *mut *mut libc::passwd) -> c_int;
+    fn getpwent() -> *mut libc::passwd;
+    fn setpwent() -> ();
+    fn endpwent() -> ();
+    fn getgrgid(gid: libc::gid_t) -> *mut libc::group;
+    fn getgrnam(name: *const c_char) -> *mut libc::group;
+    fn getgrgid_r(gid: libc::gid_t, grp: *mut libc::group, buf: *mut c_char, len: libc::size_t, result: *mut *mut libc::group) -> c_int;
+    fn getgrnam_r(name: *const c_char, grp: *mut libc::group, buf: *mut c_char, len: libc::size_t, result: *mut *mut libc::group) -> c_int;
+    fn getgrent() -> *mut libc::group;
+    fn setgrent() -> ();
+    fn endgrent() -> ();
+    fn getgrouplist(user: *const c_char, group: libc::gid_t, groups: *mut libc::gid_t, count: *mut c_int) -> c_int;
+    fn getlogin() -> *mut c_char;
+}
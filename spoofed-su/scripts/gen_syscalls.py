@@ -0,0 +1,147 @@
+#!/usr/bin/env python3
+"""Generate src/syscalls.rs from the kernel's syscall tables.
+
+Usage: scripts/gen_syscalls.py <linux-source-dir> > src/syscalls.rs
+
+Every architecture minsuki supports describes its syscalls in a
+syscall.tbl file (arm64 and riscv share scripts/syscall.tbl since 6.11).
+Only the syscalls listed in WANTED end up in the generated module. Each
+one becomes a Sysno variant named after its kernel entry point, so the
+16-bit uid variants on arm/x86 (sys_chown16) stay distinct from the
+32-bit ones (sys_chown), and so do the different stat layouts.
+"""
+
+import os
+import sys
+
+# (target_arch, table, ABIs included in the build of that architecture)
+ARCHES = [
+    ("aarch64", "scripts/syscall.tbl",
+     {"common", "64", "renameat", "rlimit", "memfd_secret"}),
+    ("arm", "arch/arm/tools/syscall.tbl", {"common", "eabi"}),
+    ("riscv64", "scripts/syscall.tbl",
+     {"common", "64", "riscv", "rlimit", "memfd_secret"}),
+    ("x86", "arch/x86/entry/syscalls/syscall_32.tbl", {"i386"}),
+    ("x86_64", "arch/x86/entry/syscalls/syscall_64.tbl", {"common", "64"}),
+]
+
+WANTED = [
+    # ownership and permissions
+    "chown", "chown32", "lchown", "lchown32", "fchown", "fchown32", "fchownat",
+    "chmod", "fchmod", "fchmodat", "fchmodat2",
+    "access", "faccessat", "faccessat2",
+    # credentials
+    "getuid", "getuid32", "geteuid", "geteuid32",
+    "getgid", "getgid32", "getegid", "getegid32",
+    "setuid", "setuid32", "setgid", "setgid32",
+    "setreuid", "setreuid32", "setregid", "setregid32",
+    "setresuid", "setresuid32", "getresuid", "getresuid32",
+    "setresgid", "setresgid32", "getresgid", "getresgid32",
+    "setfsuid", "setfsuid32", "setfsgid", "setfsgid32",
+    "getgroups", "getgroups32", "setgroups", "setgroups32",
+    # stat family
+    "stat", "lstat", "fstat", "stat64", "lstat64", "fstat64",
+    "newfstatat", "fstatat64", "statx",
+    # capabilities
+    "capget", "capset", "prctl",
+    # file creation
+    "open", "openat", "openat2", "creat",
+    "mkdir", "mkdirat", "mknod", "mknodat",
+    "symlink", "symlinkat", "link", "linkat",
+    # process lifetime
+    "exit_group",
+]
+
+
+def parse_table(path, abis):
+    """Return {name: (number, entry point)} for the wanted syscalls"""
+    table = {}
+    with open(path) as f:
+        for line in f:
+            line = line.split("#", 1)[0].strip()
+            if not line:
+                continue
+            fields = line.split()
+            if len(fields) < 4 or fields[1] not in abis:
+                continue
+            number, _, name, entry = fields[:4]
+            if name in WANTED:
+                table[name] = (int(number), entry)
+    return table
+
+
+def variant(entry):
+    """sys_fchownat -> Fchownat, sys_chown16 -> Chown16"""
+    name = entry[len("sys_"):] if entry.startswith("sys_") else entry
+    return "".join(part.capitalize() for part in name.split("_"))
+
+
+def render(tables):
+    variants = sorted({variant(entry)
+                       for table in tables.values()
+                       for _, entry in table.values()})
+    out = []
+    emit = out.append
+
+    emit("// This file is @generated by scripts/gen_syscalls.py from the kernel's")
+    emit("// syscall tables. Do not edit it by hand; rerun the script instead.")
+    emit("")
+    emit("/// Syscalls the interceptor cares about, named after their kernel entry point")
+    emit("#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]")
+    emit("pub enum Sysno {")
+    for v in variants:
+        emit("    %s," % v)
+    emit("}")
+
+    for arch, table in tables.items():
+        rows = sorted((number, name, variant(entry))
+                      for name, (number, entry) in table.items())
+        emit("")
+        emit('#[cfg(target_arch = "%s")]' % arch)
+        emit("impl Sysno {")
+        emit("    /// Every known syscall of this architecture, ordered by number")
+        emit("    pub const ALL: &'static [Sysno] = &[")
+        for _, _, v in rows:
+            emit("        Sysno::%s," % v)
+        emit("    ];")
+        emit("")
+        emit("    pub fn from_number(number: i64) -> Option<Self> {")
+        emit("        match number {")
+        for number, _, v in rows:
+            emit("            %d => Some(Sysno::%s)," % (number, v))
+        emit("            _ => None,")
+        emit("        }")
+        emit("    }")
+        emit("")
+        emit("    pub fn number(self) -> Option<i64> {")
+        emit("        match self {")
+        for number, _, v in rows:
+            emit("            Sysno::%s => Some(%d)," % (v, number))
+        emit("            _ => None,")
+        emit("        }")
+        emit("    }")
+        emit("")
+        emit("    /// The syscall's name in this architecture's table")
+        emit("    pub fn name(self) -> &'static str {")
+        emit("        match self {")
+        for _, name, v in rows:
+            emit('            Sysno::%s => "%s",' % (v, name))
+        emit('            _ => "unknown",')
+        emit("        }")
+        emit("    }")
+        emit("}")
+
+    return "\n".join(out) + "\n"
+
+
+def main():
+    if len(sys.argv) != 2:
+        sys.exit(__doc__.strip().splitlines()[2])
+    linux = sys.argv[1]
+    tables = {arch: parse_table(os.path.join(linux, path), abis)
+              for arch, path, abis in ARCHES}
+    sys.stdout.write(render(tables))
+
+
+if __name__ == "__main__":
+    main()
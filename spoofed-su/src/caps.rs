@@ -0,0 +1,522 @@
+use crate::types::{MinSukiError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::{BitAnd, BitOr, Not};
+
+/// Linux capabilities, numbered as in <linux/capability.h>
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum Capability {
+    Chown = 0,
+    DacOverride = 1,
+    DacReadSearch = 2,
+    Fowner = 3,
+    Fsetid = 4,
+    Kill = 5,
+    Setgid = 6,
+    Setuid = 7,
+    Setpcap = 8,
+    LinuxImmutable = 9,
+    NetBindService = 10,
+    NetBroadcast = 11,
+    NetAdmin = 12,
+    NetRaw = 13,
+    IpcLock = 14,
+    IpcOwner = 15,
+    SysModule = 16,
+    SysRawio = 17,
+    SysChroot = 18,
+    SysPtrace = 19,
+    SysPacct = 20,
+    SysAdmin = 21,
+    SysBoot = 22,
+    SysNice = 23,
+    SysResource = 24,
+    SysTime = 25,
+    SysTtyConfig = 26,
+    Mknod = 27,
+    Lease = 28,
+    AuditWrite = 29,
+    AuditControl = 30,
+    Setfcap = 31,
+    MacOverride = 32,
+    MacAdmin = 33,
+    Syslog = 34,
+    WakeAlarm = 35,
+    BlockSuspend = 36,
+    AuditRead = 37,
+    Perfmon = 38,
+    Bpf = 39,
+    CheckpointRestore = 40,
+}
+
+impl Capability {
+    /// Every capability, in numeric order
+    pub const ALL: [Capability; 41] = [
+        Self::Chown, Self::DacOverride, Self::DacReadSearch, Self::Fowner, Self::Fsetid,
+        Self::Kill, Self::Setgid, Self::Setuid, Self::Setpcap, Self::LinuxImmutable,
+        Self::NetBindService, Self::NetBroadcast, Self::NetAdmin, Self::NetRaw, Self::IpcLock,
+        Self::IpcOwner, Self::SysModule, Self::SysRawio, Self::SysChroot, Self::SysPtrace,
+        Self::SysPacct, Self::SysAdmin, Self::SysBoot, Self::SysNice, Self::SysResource,
+        Self::SysTime, Self::SysTtyConfig, Self::Mknod, Self::Lease, Self::AuditWrite,
+        Self::AuditControl, Self::Setfcap, Self::MacOverride, Self::MacAdmin, Self::Syslog,
+        Self::WakeAlarm, Self::BlockSuspend, Self::AuditRead, Self::Perfmon, Self::Bpf,
+        Self::CheckpointRestore,
+    ];
+    
+    const NAMES: [&'static str; 41] = [
+        "cap_chown", "cap_dac_override", "cap_dac_read_search", "cap_fowner", "cap_fsetid",
+        "cap_kill", "cap_setgid", "cap_setuid", "cap_setpcap", "cap_linux_immutable",
+        "cap_net_bind_service", "cap_net_broadcast", "cap_net_admin", "cap_net_raw", "cap_ipc_lock",
+        "cap_ipc_owner", "cap_sys_module", "cap_sys_rawio", "cap_sys_chroot", "cap_sys_ptrace",
+        "cap_sys_pacct", "cap_sys_admin", "cap_sys_boot", "cap_sys_nice", "cap_sys_resource",
+        "cap_sys_time", "cap_sys_tty_config", "cap_mknod", "cap_lease", "cap_audit_write",
+        "cap_audit_control", "cap_setfcap", "cap_mac_override", "cap_mac_admin", "cap_syslog",
+        "cap_wake_alarm", "cap_block_suspend", "cap_audit_read", "cap_perfmon", "cap_bpf",
+        "cap_checkpoint_restore",
+    ];
+    
+    /// The libcap name, such as `cap_chown`
+    pub fn name(self) -> &'static str {
+        Self::NAMES[self as usize]
+    }
+    
+    /// Look a capability up by libcap name, case-insensitively, or by number
+    pub fn from_name(name: &str) -> Option<Self> {
+        if let Ok(number) = name.parse::<usize>() {
+            return Self::ALL.get(number).copied();
+        }
+        let name = name.to_ascii_lowercase();
+        Self::ALL.iter().copied().find(|cap| cap.name() == name)
+    }
+    
+    pub fn from_number(number: u32) -> Option<Self> {
+        Self::ALL.get(number as usize).copied()
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A set of capabilities, laid out like the kernel's 64-bit masks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CapSet(u64);
+
+impl CapSet {
+    pub const EMPTY: CapSet = CapSet(0);
+    
+    /// Every known capability
+    pub fn full() -> Self {
+        Self((1 << Capability::ALL.len()) - 1)
+    }
+    
+    /// The set a kernel mask stands for, ignoring unknown bits
+    pub fn from_bits(bits: u64) -> Self {
+        Self(bits) & Self::full()
+    }
+    
+    pub fn bits(self) -> u64 {
+        self.0
+    }
+    
+    pub fn contains(self, cap: Capability) -> bool {
+        self.0 & (1 << cap as u8) != 0
+    }
+    
+    pub fn insert(&mut self, cap: Capability) {
+        self.0 |= 1 << cap as u8;
+    }
+    
+    pub fn remove(&mut self, cap: Capability) {
+        self.0 &= !(1 << cap as u8);
+    }
+    
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+    
+    pub fn iter(self) -> impl Iterator<Item = Capability> {
+        Capability::ALL.into_iter().filter(move |cap| self.contains(*cap))
+    }
+}
+
+impl BitAnd for CapSet {
+    type Output = Self;
+    
+    fn bitand(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+}
+
+impl BitOr for CapSet {
+    type Output = Self;
+    
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+impl Not for CapSet {
+    type Output = Self;
+    
+    fn not(self) -> Self {
+        Self(!self.0) & Self::full()
+    }
+}
+
+impl FromIterator<Capability> for CapSet {
+    fn from_iter<I: IntoIterator<Item = Capability>>(iter: I) -> Self {
+        let mut set = Self::EMPTY;
+        for cap in iter {
+            set.insert(cap);
+        }
+        set
+    }
+}
+
+/// Comma-separated capability names, as capsh prints the bounding set
+impl fmt::Display for CapSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<_> = self.iter().map(Capability::name).collect();
+        f.write_str(&names.join(","))
+    }
+}
+
+/// The capabilities that follow the filesystem UID in and out of 0
+fn fs_set() -> CapSet {
+    [
+        Capability::Chown,
+        Capability::DacOverride,
+        Capability::DacReadSearch,
+        Capability::Fowner,
+        Capability::Fsetid,
+        Capability::LinuxImmutable,
+        Capability::Mknod,
+        Capability::MacOverride,
+    ]
+    .into_iter()
+    .collect()
+}
+
+/// Capabilities attached to an executable (the security.capability xattr)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct FileCaps {
+    pub permitted: CapSet,
+    pub inheritable: CapSet,
+    
+    /// Whether the new permitted set becomes effective right away
+    pub effective: bool,
+}
+
+impl FileCaps {
+    pub fn is_empty(&self) -> bool {
+        self.permitted.is_empty() && self.inheritable.is_empty()
+    }
+    
+    /// Parse the `cap_from_text` syntax setcap accepts, e.g. `cap_net_raw=ep`
+    pub fn from_text(text: &str) -> Result<Self> {
+        let mut sets = CapState::default();
+        sets.apply_text(text)?;
+        Ok(Self {
+            permitted: sets.permitted,
+            inheritable: sets.inheritable,
+            effective: !sets.effective.is_empty(),
+        })
+    }
+}
+
+/// Printed the way getcap does
+impl fmt::Display for FileCaps {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sets = CapState {
+            permitted: self.permitted,
+            inheritable: self.inheritable,
+            effective: if self.effective { self.permitted | self.inheritable } else { CapSet::EMPTY },
+            ..CapState::default()
+        };
+        write!(f, "{}", sets)
+    }
+}
+
+/// The five capability sets of a process
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct CapState {
+    pub permitted: CapSet,
+    pub effective: CapSet,
+    pub inheritable: CapSet,
+    pub bounding: CapSet,
+    pub ambient: CapSet,
+}
+
+impl CapState {
+    /// The sets of a process running as root
+    pub fn root() -> Self {
+        Self {
+            permitted: CapSet::full(),
+            effective: CapSet::full(),
+            bounding: CapSet::full(),
+            ..Self::default()
+        }
+    }
+    
+    /// Apply `cap_from_text` clauses to the effective, inheritable and
+    /// permitted sets. A clause is a capability list followed by operators:
+    /// `=` clears the listed capabilities from all three sets before
+    /// raising the flags after it, `+` raises and `-` lowers them. An empty
+    /// list (only allowed before `=`) or `all` stands for every capability.
+    pub fn apply_text(&mut self, text: &str) -> Result<()> {
+        let invalid = || MinSukiError::Capability(text.to_string());
+        let is_op = |c: char| matches!(c, '=' | '+' | '-');
+        
+        for clause in text.split_whitespace() {
+            let split = clause.find(is_op).ok_or_else(invalid)?;
+            let (names, mut actions) = clause.split_at(split);
+            let caps = if names.is_empty() || names.eq_ignore_ascii_case("all") {
+                if names.is_empty() && !actions.starts_with('=') {
+                    return Err(invalid());
+                }
+                CapSet::full()
+            } else {
+                names
+                    .split(',')
+                    .map(|name| Capability::from_name(name).ok_or_else(invalid))
+                    .collect::<Result<CapSet>>()?
+            };
+            
+            while let Some(op) = actions.chars().next() {
+                let rest = &actions[1..];
+                let end = rest.find(is_op).unwrap_or(rest.len());
+                let flags = &rest[..end];
+                actions = &rest[end..];
+                
+                if op == '=' {
+                    for set in [&mut self.effective, &mut self.inheritable, &mut self.permitted] {
+                        *set = *set & !caps;
+                    }
+                }
+                for flag in flags.chars() {
+                    let set = match flag {
+                        'e' => &mut self.effective,
+                        'i' => &mut self.inheritable,
+                        'p' => &mut self.permitted,
+                        _ => return Err(invalid()),
+                    };
+                    *set = if op == '-' { *set & !caps } else { *set | caps };
+                }
+            }
+        }
+        Ok(())
+    }
+    
+    /// Sets described by `cap_from_text` syntax, with a full bounding set
+    pub fn from_text(text: &str) -> Result<Self> {
+        let mut sets = Self { bounding: CapSet::full(), ..Self::default() };
+        sets.apply_text(text)?;
+        Ok(sets)
+    }
+    
+    /// The capability sets after execve, following capabilities(7):
+    ///
+    /// ```text
+    /// P'(ambient)     = file is privileged ? 0 : P(ambient)
+    /// P'(permitted)   = (P(inheritable) & F(inheritable)) |
+    ///                   (F(permitted) & P(bounding)) | P'(ambient)
+    /// P'(effective)   = F(effective) ? P'(permitted) : P'(ambient)
+    /// P'(inheritable) = P(inheritable)
+    /// P'(bounding)    = P(bounding)
+    /// ```
+    ///
+    /// A file is privileged when it has capabilities or `setid` says it
+    /// changed the process's ids. `ruid` and `euid` are the ids after the
+    /// exec; root gets the file sets treated as full, and an effective
+    /// root UID an effective bit as well.
+    pub fn exec(&self, file: &FileCaps, setid: bool, ruid: u32, euid: u32) -> Self {
+        let privileged = setid || !file.is_empty();
+        let mut file = *file;
+        if ruid == 0 || euid == 0 {
+            file.permitted = CapSet::full();
+            file.inheritable = CapSet::full();
+        }
+        if euid == 0 {
+            file.effective = true;
+        }
+        
+        let ambient = if privileged { CapSet::EMPTY } else { self.ambient };
+        let permitted = (self.inheritable & file.inheritable) | (file.permitted & self.bounding) | ambient;
+        Self {
+            permitted,
+            effective: if file.effective { permitted } else { ambient },
+            inheritable: self.inheritable,
+            bounding: self.bounding,
+            ambient,
+        }
+    }
+    
+    /// Adjust the sets after a change of UIDs, like the kernel does for a
+    /// process without SECURE_NO_SETUID_FIXUP. Leaving UID 0 behind for
+    /// good clears the permitted set unless `keep_caps` is set
+    pub fn fix_setuid(&mut self, old: [u32; 3], new: [u32; 3], keep_caps: bool) {
+        let [_, old_euid, _] = old;
+        let [_, new_euid, _] = new;
+        
+        if old.contains(&0) && !new.contains(&0) {
+            if !keep_caps {
+                self.permitted = CapSet::EMPTY;
+                self.effective = CapSet::EMPTY;
+            }
+            self.ambient = CapSet::EMPTY;
+        }
+        if old_euid == 0 && new_euid != 0 {
+            self.effective = CapSet::EMPTY;
+        }
+        if old_euid != 0 && new_euid == 0 {
+            self.effective = self.permitted;
+        }
+    }
+    
+    /// Adjust the effective set after a change of filesystem UID
+    pub fn fix_setfsuid(&mut self, old: u32, new: u32) {
+        if old == 0 && new != 0 {
+            self.effective = self.effective & !fs_set();
+        }
+        if old != 0 && new == 0 {
+            self.effective = self.effective | (self.permitted & fs_set());
+        }
+    }
+    
+    /// The effective, permitted and inheritable sets the way capget(2)
+    /// fills in its data array: `words` entries of three 32-bit masks,
+    /// lowest capabilities first
+    pub fn user_data(&self, words: usize) -> Vec<u32> {
+        (0..words)
+            .flat_map(|word| {
+                let shift = 32 * word;
+                [self.effective, self.permitted, self.inheritable].map(|set| (set.bits() >> shift) as u32)
+            })
+            .collect()
+    }
+    
+    /// Replace the effective, permitted and inheritable sets with the data
+    /// array of a capset(2) call, under the kernel's rules: permitted can
+    /// only shrink, effective must stay within it, and inheritable may
+    /// only grow from permitted with CAP_SETPCAP and never beyond bounding
+    pub fn capset(&mut self, data: &[u32]) -> Result<()> {
+        let set = |index: usize| {
+            let bits = data
+                .chunks_exact(3)
+                .enumerate()
+                .fold(0u64, |bits, (word, entry)| bits | (entry[index] as u64) << (32 * word));
+            CapSet::from_bits(bits)
+        };
+        let (effective, permitted, inheritable) = (set(0), set(1), set(2));
+        
+        let within = |set: CapSet, limit: CapSet| (set & !limit).is_empty();
+        let setpcap = self.effective.contains(Capability::Setpcap);
+        if !(setpcap || within(inheritable, self.inheritable | self.permitted))
+            || !within(inheritable, self.inheritable | self.bounding)
+            || !within(permitted, self.permitted)
+            || !within(effective, permitted)
+        {
+            return Err(MinSukiError::PermissionDenied);
+        }
+        
+        self.effective = effective;
+        self.permitted = permitted;
+        self.inheritable = inheritable;
+        self.ambient = self.ambient & permitted & inheritable;
+        Ok(())
+    }
+}
+
+/// Header versions of capget(2) and capset(2)
+pub const VERSION_1: u32 = 0x1998_0330;
+pub const VERSION_2: u32 = 0x2007_1026;
+pub const VERSION_3: u32 = 0x2008_0522;
+
+/// How many 32-bit words per set a capget/capset header version moves
+pub fn version_words(version: u32) -> Option<usize> {
+    match version {
+        VERSION_1 => Some(1),
+        VERSION_2 | VERSION_3 => Some(2),
+        _ => None,
+    }
+}
+
+/// The effective, inheritable and permitted sets in `cap_to_text` syntax,
+/// which `cap_from_text` parses back, e.g. `=ep` for root
+impl fmt::Display for CapState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut groups: BTreeMap<String, CapSet> = BTreeMap::new();
+        for cap in Capability::ALL {
+            let flags: String = [('e', self.effective), ('i', self.inheritable), ('p', self.permitted)]
+                .iter()
+                .filter(|(_, set)| set.contains(cap))
+                .map(|(flag, _)| *flag)
+                .collect();
+            if !flags.is_empty() {
+                groups.entry(flags).or_default().insert(cap);
+            }
+        }
+        if groups.is_empty() {
+            return f.write_str("=");
+        }
+        
+        // Capabilities held with the same flags make up one clause; one that
+        // covers everything goes first, without a list
+        let mut clauses: Vec<_> = groups
+            .into_iter()
+            .map(|(flags, caps)| if caps == CapSet::full() { format!("={}", flags) } else { format!("{}={}", caps, flags) })
+            .collect();
+        clauses.sort_by_key(|clause| !clause.starts_with('='));
+        f.write_str(&clauses.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_text_round_trip() {
+        assert_eq!(CapState::root().to_string(), "=ep");
+        assert_eq!(CapState::default().to_string(), "=");
+        
+        let sets = CapState::from_text("=ep cap_net_raw-e cap_chown+i").unwrap();
+        assert!(sets.permitted.contains(Capability::NetRaw));
+        assert!(!sets.effective.contains(Capability::NetRaw));
+        assert!(sets.inheritable.contains(Capability::Chown));
+        assert_eq!(CapState::from_text(&sets.to_string()).unwrap(), sets);
+        
+        assert!(CapState::from_text("cap_bogus=ep").is_err());
+        assert!(CapState::from_text("+ep").is_err());
+        assert!(CapState::from_text("cap_chown=x").is_err());
+    }
+    
+    #[test]
+    fn test_exec_transitions() {
+        let file = FileCaps::from_text("cap_net_raw=ep").unwrap();
+        let user = CapState { bounding: CapSet::full(), ..CapState::default() };
+        
+        // Root keeps everything through a plain exec
+        assert_eq!(CapState::root().exec(&FileCaps::default(), false, 0, 0), CapState::root());
+        
+        // An unprivileged process gains exactly the file's capabilities
+        let after = user.exec(&file, false, 1000, 1000);
+        assert_eq!(after.effective.iter().collect::<Vec<_>>(), vec![Capability::NetRaw]);
+        
+        // and loses them again on the next plain exec
+        assert!(after.exec(&FileCaps::default(), false, 1000, 1000).permitted.is_empty());
+        
+        // Ambient capabilities survive a plain exec but not a privileged one
+        let mut ambient = user;
+        ambient.inheritable.insert(Capability::Kill);
+        ambient.ambient.insert(Capability::Kill);
+        assert!(ambient.exec(&FileCaps::default(), false, 1000, 1000).effective.contains(Capability::Kill));
+        assert!(!ambient.exec(&file, false, 1000, 1000).effective.contains(Capability::Kill));
+    }
+}
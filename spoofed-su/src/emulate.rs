@@ -0,0 +1,998 @@
+use crate::caps::{self, FileCaps};
+use crate::memory::TraceeMemory;
+use crate::paths;
+use crate::stat::{self, StatLayout};
+use crate::state::StateManager;
+use crate::syscalls::Sysno;
+use crate::trace::TraceArgs;
+use crate::types::{Credentials, FakeMetadata, MinSukiError, Result};
+use nix::errno::Errno;
+use nix::unistd::Pid;
+use std::collections::HashMap;
+use std::fs::Metadata;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// What a backend should do with an intercepted syscall
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Action {
+    /// Let the kernel run the syscall and report its real result
+    PassThrough,
+    
+    /// Let the kernel run the syscall, then adjust its result at exit
+    /// (only a ptrace backend sees the exit)
+    Inspect,
+    
+    /// Cancel the syscall and report this result at exit instead
+    Emulate(i64),
+}
+
+/// A change to the fake state or the caller's memory that a decision
+/// makes once it is applied
+#[derive(Debug, Clone)]
+pub(crate) enum Effect {
+    None,
+    
+    /// Record new fake metadata for a file
+    Metadata(PathBuf, FakeMetadata),
+    
+    /// Give the caller a new fake identity
+    Credentials(Credentials),
+    
+    /// Write the caller's identity down for children that have none yet
+    HandDown,
+    
+    /// Store results in the caller's memory; if that fails the syscall
+    /// fails with EFAULT instead
+    Write(Vec<(u64, Vec<u8>)>),
+}
+
+/// How to handle an intercepted syscall, and what handling it changes
+#[derive(Debug, Clone)]
+pub(crate) struct Decision {
+    pub(crate) action: Action,
+    pub(crate) effect: Effect,
+}
+
+impl Decision {
+    fn new(action: Action, effect: Effect) -> Self {
+        Self { action, effect }
+    }
+}
+
+impl From<Action> for Decision {
+    fn from(action: Action) -> Self {
+        Self::new(action, Effect::None)
+    }
+}
+
+/// Where an emulated syscall finds the file it operates on
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Target {
+    /// A path in tracee memory, relative to `dirfd` unless absolute, with
+    /// the *at syscall flags (AT_SYMLINK_NOFOLLOW, AT_EMPTY_PATH)
+    Path { dirfd: i32, ptr: u64, flags: i32 },
+    
+    /// An open file descriptor of the tracee
+    Fd(i32),
+}
+
+impl Target {
+    fn path(ptr: u64, flags: i32) -> Self {
+        Self::Path { dirfd: libc::AT_FDCWD, ptr, flags }
+    }
+    
+    fn at(dirfd: u64, ptr: u64, flags: u64) -> Self {
+        Self::Path { dirfd: dirfd as i32, ptr, flags: flags as i32 }
+    }
+}
+
+/// What a creating syscall makes, with the mode it asks for
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum NewNode {
+    /// A regular file, which open and openat only create with O_CREAT
+    File { flags: i32, mode: u32 },
+    
+    /// A regular file from openat2, whose flags and mode are in a struct
+    /// open_how in tracee memory
+    FileHow(u64),
+    
+    Directory(u32),
+    
+    /// A device, FIFO or socket; the mode includes the file type
+    Device(u32),
+    
+    Symlink,
+    
+    /// A new name for an existing file, which keeps that file's owner
+    Link(Target),
+}
+
+/// Syscalls the interceptor knows how to emulate, with decoded arguments
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Syscall {
+    Chown { target: Target, uid: u32, gid: u32 },
+    Chmod { target: Target, mode: u32 },
+    Access { target: Target, mode: i32, flags: i32 },
+    Stat { target: Target, buf: u64, layout: &'static StatLayout },
+    Setuid(u32),
+    Setgid(u32),
+    Setreuid { ruid: u32, euid: u32 },
+    Setregid { rgid: u32, egid: u32 },
+    Setresuid { ruid: u32, euid: u32, suid: u32 },
+    Setresgid { rgid: u32, egid: u32, sgid: u32 },
+    Setfsuid(u32),
+    Setfsgid(u32),
+    // `narrow` marks the legacy 16-bit id variants on arm and x86
+    Getuid { narrow: bool },
+    Geteuid { narrow: bool },
+    Getgid { narrow: bool },
+    Getegid { narrow: bool },
+    // The three ids are written to tracee memory
+    Getresuid { ptrs: [u64; 3], narrow: bool },
+    Getresgid { ptrs: [u64; 3], narrow: bool },
+    Getgroups { size: i32, list: u64, narrow: bool },
+    Setgroups { size: i32, list: u64, narrow: bool },
+    // Header and data pointers of the capability syscalls
+    Capget { header: u64, data: u64 },
+    Capset { header: u64, data: u64 },
+    Prctl { option: i32, args: [u64; 4] },
+    // The kernel creates the file; its fake owner is recorded at exit
+    Create { target: Target, node: NewNode },
+    ExitGroup,
+}
+
+impl Syscall {
+    /// Dispatch table from kernel syscalls to emulated syscalls
+    pub(crate) fn decode(sysno: Sysno, args: &[u64; 6]) -> Option<Self> {
+        let id = |arg: u64| arg as u32;
+        let id16 = |arg: u64| match arg as u16 {
+            u16::MAX => u32::MAX,
+            id => id as u32,
+        };
+        let nofollow = libc::AT_SYMLINK_NOFOLLOW as u64;
+        
+        let syscall = match sysno {
+            Sysno::Chown => Self::Chown { target: Target::path(args[0], 0), uid: id(args[1]), gid: id(args[2]) },
+            Sysno::Chown16 => Self::Chown { target: Target::path(args[0], 0), uid: id16(args[1]), gid: id16(args[2]) },
+            Sysno::Lchown => Self::Chown { target: Target::at(libc::AT_FDCWD as u64, args[0], nofollow), uid: id(args[1]), gid: id(args[2]) },
+            Sysno::Lchown16 => Self::Chown { target: Target::at(libc::AT_FDCWD as u64, args[0], nofollow), uid: id16(args[1]), gid: id16(args[2]) },
+            Sysno::Fchown => Self::Chown { target: Target::Fd(args[0] as i32), uid: id(args[1]), gid: id(args[2]) },
+            Sysno::Fchown16 => Self::Chown { target: Target::Fd(args[0] as i32), uid: id16(args[1]), gid: id16(args[2]) },
+            Sysno::Fchownat => Self::Chown { target: Target::at(args[0], args[1], args[4]), uid: id(args[2]), gid: id(args[3]) },
+            
+            Sysno::Chmod => Self::Chmod { target: Target::path(args[0], 0), mode: args[1] as u32 },
+            Sysno::Fchmod => Self::Chmod { target: Target::Fd(args[0] as i32), mode: args[1] as u32 },
+            // The fchmodat syscall has no flags argument; libc emulates them
+            Sysno::Fchmodat => Self::Chmod { target: Target::at(args[0], args[1], 0), mode: args[2] as u32 },
+            Sysno::Fchmodat2 => Self::Chmod { target: Target::at(args[0], args[1], args[3]), mode: args[2] as u32 },
+            
+            Sysno::Access => Self::Access { target: Target::path(args[0], 0), mode: args[1] as i32, flags: 0 },
+            Sysno::Faccessat => Self::Access { target: Target::at(args[0], args[1], 0), mode: args[2] as i32, flags: 0 },
+            Sysno::Faccessat2 => Self::Access { target: Target::at(args[0], args[1], args[3]), mode: args[2] as i32, flags: args[3] as i32 },
+            
+            Sysno::Newstat => Self::Stat { target: Target::path(args[0], 0), buf: args[1], layout: &stat::STAT },
+            Sysno::Newlstat => Self::Stat { target: Target::at(libc::AT_FDCWD as u64, args[0], nofollow), buf: args[1], layout: &stat::STAT },
+            Sysno::Newfstat => Self::Stat { target: Target::Fd(args[0] as i32), buf: args[1], layout: &stat::STAT },
+            Sysno::Newfstatat => Self::Stat { target: Target::at(args[0], args[1], args[3]), buf: args[2], layout: &stat::STAT },
+            Sysno::Stat64 => Self::Stat { target: Target::path(args[0], 0), buf: args[1], layout: &stat::STAT64 },
+            Sysno::Lstat64 => Self::Stat { target: Target::at(libc::AT_FDCWD as u64, args[0], nofollow), buf: args[1], layout: &stat::STAT64 },
+            Sysno::Fstat64 => Self::Stat { target: Target::Fd(args[0] as i32), buf: args[1], layout: &stat::STAT64 },
+            Sysno::Fstatat64 => Self::Stat { target: Target::at(args[0], args[1], args[3]), buf: args[2], layout: &stat::STAT64 },
+            Sysno::Statx => Self::Stat { target: Target::at(args[0], args[1], args[2]), buf: args[4], layout: &stat::STATX },
+            
+            Sysno::Setuid => Self::Setuid(id(args[0])),
+            Sysno::Setuid16 => Self::Setuid(id16(args[0])),
+            Sysno::Setgid => Self::Setgid(id(args[0])),
+            Sysno::Setgid16 => Self::Setgid(id16(args[0])),
+            Sysno::Setreuid => Self::Setreuid { ruid: id(args[0]), euid: id(args[1]) },
+            Sysno::Setreuid16 => Self::Setreuid { ruid: id16(args[0]), euid: id16(args[1]) },
+            Sysno::Setregid => Self::Setregid { rgid: id(args[0]), egid: id(args[1]) },
+            Sysno::Setregid16 => Self::Setregid { rgid: id16(args[0]), egid: id16(args[1]) },
+            Sysno::Setresuid => Self::Setresuid { ruid: id(args[0]), euid: id(args[1]), suid: id(args[2]) },
+            Sysno::Setresuid16 => Self::Setresuid { ruid: id16(args[0]), euid: id16(args[1]), suid: id16(args[2]) },
+            Sysno::Setresgid => Self::Setresgid { rgid: id(args[0]), egid: id(args[1]), sgid: id(args[2]) },
+            Sysno::Setresgid16 => Self::Setresgid { rgid: id16(args[0]), egid: id16(args[1]), sgid: id16(args[2]) },
+            Sysno::Setfsuid => Self::Setfsuid(id(args[0])),
+            Sysno::Setfsuid16 => Self::Setfsuid(id16(args[0])),
+            Sysno::Setfsgid => Self::Setfsgid(id(args[0])),
+            Sysno::Setfsgid16 => Self::Setfsgid(id16(args[0])),
+            Sysno::Setgroups => Self::Setgroups { size: args[0] as i32, list: args[1], narrow: false },
+            Sysno::Setgroups16 => Self::Setgroups { size: args[0] as i32, list: args[1], narrow: true },
+            
+            Sysno::Getuid => Self::Getuid { narrow: false },
+            Sysno::Getuid16 => Self::Getuid { narrow: true },
+            Sysno::Geteuid => Self::Geteuid { narrow: false },
+            Sysno::Geteuid16 => Self::Geteuid { narrow: true },
+            Sysno::Getgid => Self::Getgid { narrow: false },
+            Sysno::Getgid16 => Self::Getgid { narrow: true },
+            Sysno::Getegid => Self::Getegid { narrow: false },
+            Sysno::Getegid16 => Self::Getegid { narrow: true },
+            Sysno::Getresuid => Self::Getresuid { ptrs: [args[0], args[1], args[2]], narrow: false },
+            Sysno::Getresuid16 => Self::Getresuid { ptrs: [args[0], args[1], args[2]], narrow: true },
+            Sysno::Getresgid => Self::Getresgid { ptrs: [args[0], args[1], args[2]], narrow: false },
+            Sysno::Getresgid16 => Self::Getresgid { ptrs: [args[0], args[1], args[2]], narrow: true },
+            Sysno::Getgroups => Self::Getgroups { size: args[0] as i32, list: args[1], narrow: false },
+            Sysno::Getgroups16 => Self::Getgroups { size: args[0] as i32, list: args[1], narrow: true },
+            
+            Sysno::Capget => Self::Capget { header: args[0], data: args[1] },
+            Sysno::Capset => Self::Capset { header: args[0], data: args[1] },
+            Sysno::Prctl => Self::Prctl { option: args[0] as i32, args: [args[1], args[2], args[3], args[4]] },
+            
+            Sysno::Open => Self::Create { target: Target::path(args[0], 0), node: NewNode::File { flags: args[1] as i32, mode: args[2] as u32 } },
+            Sysno::Openat => Self::Create { target: Target::at(args[0], args[1], 0), node: NewNode::File { flags: args[2] as i32, mode: args[3] as u32 } },
+            Sysno::Openat2 => Self::Create { target: Target::at(args[0], args[1], 0), node: NewNode::FileHow(args[2]) },
+            Sysno::Creat => Self::Create { target: Target::path(args[0], 0), node: NewNode::File { flags: libc::O_CREAT, mode: args[1] as u32 } },
+            Sysno::Mkdir => Self::Create { target: Target::path(args[0], nofollow as i32), node: NewNode::Directory(args[1] as u32) },
+            Sysno::Mkdirat => Self::Create { target: Target::at(args[0], args[1], nofollow), node: NewNode::Directory(args[2] as u32) },
+            Sysno::Mknod => Self::Create { target: Target::path(args[0], nofollow as i32), node: NewNode::Device(args[1] as u32) },
+            Sysno::Mknodat => Self::Create { target: Target::at(args[0], args[1], nofollow), node: NewNode::Device(args[2] as u32) },
+            Sysno::Symlink => Self::Create { target: Target::path(args[1], nofollow as i32), node: NewNode::Symlink },
+            Sysno::Symlinkat => Self::Create { target: Target::at(args[1], args[2], nofollow), node: NewNode::Symlink },
+            Sysno::Link => Self::Create { target: Target::path(args[1], nofollow as i32), node: NewNode::Link(Target::path(args[0], nofollow as i32)) },
+            Sysno::Linkat => {
+                // Only AT_SYMLINK_FOLLOW makes linkat follow the old path
+                let follow = args[4] & libc::AT_SYMLINK_FOLLOW as u64 != 0;
+                let source = Target::at(args[0], args[1], if follow { args[4] & libc::AT_EMPTY_PATH as u64 } else { nofollow | args[4] });
+                Self::Create { target: Target::at(args[2], args[3], nofollow), node: NewNode::Link(source) }
+            }
+            
+            Sysno::ExitGroup => Self::ExitGroup,
+        };
+        
+        Some(syscall)
+    }
+    
+    /// Numbers of the native syscalls `decode` understands, narrowed down
+    /// by `wanted` to what a backend can handle
+    pub(crate) fn intercepted(wanted: impl Fn(&Syscall) -> bool) -> Vec<i64> {
+        Sysno::ALL
+            .iter()
+            .filter(|sysno| Self::decode(**sysno, &[0; 6]).is_some_and(|syscall| wanted(&syscall)))
+            .filter_map(|sysno| sysno.number())
+            .collect()
+    }
+    
+    /// The target and ids of the syscall, for the trace log
+    pub(crate) fn trace_args(&self, emulator: &Emulator, pid: Pid) -> TraceArgs {
+        let mut args = TraceArgs::default();
+        let target = match *self {
+            Self::Chown { target, uid, gid } => {
+                args.uid = Some(uid);
+                args.gid = Some(gid);
+                Some(target)
+            }
+            Self::Chmod { target, mode } => {
+                args.mode = Some(mode);
+                Some(target)
+            }
+            Self::Access { target, mode, .. } => {
+                args.mode = Some(mode as u32);
+                Some(target)
+            }
+            Self::Stat { target, .. } | Self::Create { target, .. } => Some(target),
+            Self::Setuid(uid) | Self::Setfsuid(uid) => {
+                args.uid = Some(uid);
+                None
+            }
+            Self::Setgid(gid) | Self::Setfsgid(gid) => {
+                args.gid = Some(gid);
+                None
+            }
+            Self::Setreuid { euid, .. } | Self::Setresuid { euid, .. } => {
+                args.uid = Some(euid);
+                None
+            }
+            Self::Setregid { egid, .. } | Self::Setresgid { egid, .. } => {
+                args.gid = Some(egid);
+                None
+            }
+            Self::Prctl { option, args: [arg, ..] } => {
+                args.option = Some(option);
+                args.arg = Some(arg);
+                None
+            }
+            _ => None,
+        };
+        
+        match target {
+            Some(Target::Fd(fd)) => {
+                args.fd = Some(fd);
+                args.path = emulator.target_path(pid, Target::Fd(fd)).ok();
+            }
+            Some(target) => args.path = emulator.target_path(pid, target).ok(),
+            None => {}
+        }
+        args
+    }
+    
+    /// Whether answering the syscall requires its real result
+    pub(crate) fn needs_exit(&self) -> bool {
+        matches!(self, Self::Access { .. } | Self::Stat { .. } | Self::Create { .. })
+    }
+}
+
+/// Return value of a get*id syscall, folding ids that do not fit the
+/// legacy 16-bit variants to the kernel's overflow id
+fn id_return(id: u32, narrow: bool) -> i64 {
+    const OVERFLOW_ID: u32 = 65534;
+    if narrow && id > u16::MAX as u32 {
+        OVERFLOW_ID as i64
+    } else {
+        id as i64
+    }
+}
+
+/// The in-memory representation of an id in a tracee's buffer
+fn id_bytes(id: u32, narrow: bool) -> Vec<u8> {
+    if narrow {
+        (id_return(id, narrow) as u16).to_ne_bytes().to_vec()
+    } else {
+        id.to_ne_bytes().to_vec()
+    }
+}
+
+/// Store getres*id results through the caller's three pointers
+fn write_ids(ptrs: [u64; 3], ids: [u32; 3], narrow: bool) -> Decision {
+    let writes = ptrs.into_iter().zip(ids).map(|(ptr, id)| (ptr, id_bytes(id, narrow))).collect();
+    Decision::new(Action::Emulate(0), Effect::Write(writes))
+}
+
+/// Negated errno, the way a syscall reports failure
+fn errno_return(errno: i32) -> i64 {
+    -(errno as i64)
+}
+
+/// Return value of an emulated credential change; a transition the fake
+/// identity may not make fails with EPERM, as it would in the kernel
+fn credential_return(result: Result<()>) -> Result<i64> {
+    match result {
+        Ok(()) => Ok(0),
+        Err(MinSukiError::PermissionDenied) => Ok(errno_return(libc::EPERM)),
+        Err(e) => Err(e),
+    }
+}
+
+/// Whether root may execute `path`. Root may read and write anything, but
+/// may only execute files with at least one execute bit set, fake or real
+pub fn root_may_execute(state_manager: &StateManager, path: &PathBuf) -> bool {
+    let fake_mode = state_manager.get_state().lock().unwrap().get_metadata(path).map(|meta| meta.mode);
+    let mode = match fake_mode {
+        Some(mode) => mode,
+        None => match std::fs::metadata(path) {
+            Ok(meta) if meta.is_dir() => 0o111,
+            Ok(meta) => std::os::unix::fs::PermissionsExt::mode(&meta.permissions()),
+            Err(_) => return false,
+        },
+    };
+    mode & 0o111 != 0
+}
+
+/// The file mode creation mask of a process
+fn process_umask(pid: Pid) -> u32 {
+    std::fs::read_to_string(format!("/proc/{}/status", pid))
+        .ok()
+        .and_then(|status| {
+            status
+                .lines()
+                .find_map(|line| line.strip_prefix("Umask:"))
+                .and_then(|value| u32::from_str_radix(value.trim(), 8).ok())
+        })
+        .unwrap_or(0o022)
+}
+
+/// The thread group leader and then the parent process of a thread, the
+/// two places it can have inherited its credentials from
+fn process_ancestry(pid: Pid) -> Vec<Pid> {
+    let status = match std::fs::read_to_string(format!("/proc/{}/status", pid)) {
+        Ok(status) => status,
+        Err(_) => return Vec::new(),
+    };
+    let field = |name: &str| {
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix(name))
+            .and_then(|value| value.trim().parse().ok())
+            .map(Pid::from_raw)
+    };
+    [field("Tgid:"), field("PPid:")]
+        .into_iter()
+        .flatten()
+        .filter(|ancestor| *ancestor != pid)
+        .collect()
+}
+
+/// The children of every thread of a process
+fn process_children(pid: Pid) -> Vec<Pid> {
+    let tasks = match std::fs::read_dir(format!("/proc/{}/task", pid)) {
+        Ok(tasks) => tasks,
+        Err(_) => return Vec::new(),
+    };
+    tasks
+        .flatten()
+        .filter_map(|task| std::fs::read_to_string(task.path().join("children")).ok())
+        .flat_map(|list| list.split_whitespace().filter_map(|child| child.parse().ok()).map(Pid::from_raw).collect::<Vec<_>>())
+        .collect()
+}
+
+/// Give the children of a process that have no identity yet the one it
+/// has now. They were forked with it, and it has to be written down
+/// before the process changes identity or exits and they are left to
+/// inherit the new one, or reparented and inherit nothing
+fn hand_down(credentials: &mut HashMap<Pid, Credentials>, pid: Pid, creds: &Credentials) {
+    for child in process_children(pid) {
+        credentials.entry(child).or_insert_with(|| creds.clone());
+    }
+}
+
+/// The fake-root semantics of intercepted syscalls, shared by the ptrace
+/// and seccomp backends. The calling process is inspected through /proc
+/// and its memory, so the caller only has to keep it stopped meanwhile.
+#[derive(Clone)]
+pub(crate) struct Emulator {
+    state_manager: StateManager,
+    
+    /// The fake identity of every thread that has one, keyed by tid since
+    /// the kernel keeps credentials per thread too
+    credentials: Arc<Mutex<HashMap<Pid, Credentials>>>,
+}
+
+impl Emulator {
+    pub(crate) fn new(state_manager: StateManager) -> Self {
+        Self { state_manager, credentials: Arc::new(Mutex::new(HashMap::new())) }
+    }
+    
+    pub(crate) fn state_manager(&self) -> &StateManager {
+        &self.state_manager
+    }
+    
+    /// Decide how to handle an intercepted syscall. This only reads the
+    /// caller and the fake state; what the emulated syscall changes is
+    /// left to `apply`, so that a backend can first make sure the caller
+    /// is still the process that made it
+    pub(crate) fn decide(&self, pid: Pid, syscall: Syscall) -> Result<Decision> {
+        let decision = match syscall {
+            Syscall::Chown { target, uid, gid } => {
+                // Without fake root, or for a path the kernel will reject
+                // anyway, the real syscall gives the right answer
+                let real = match self.stat_target(pid, target) {
+                    Ok(real) if self.is_root(pid) => real,
+                    _ => return Ok(Action::PassThrough.into()),
+                };
+                let effect = self.chowned(pid, target, &real, uid, gid)?;
+                Decision::new(Action::Emulate(0), effect)
+            }
+            Syscall::Chmod { target, mode } => {
+                let real = match self.stat_target(pid, target) {
+                    Ok(real) if self.is_root(pid) => real,
+                    _ => return Ok(Action::PassThrough.into()),
+                };
+                let effect = self.chmodded(pid, target, &real, mode)?;
+                Decision::new(Action::Emulate(0), effect)
+            }
+            Syscall::Access { .. } | Syscall::Stat { .. } => Action::Inspect.into(),
+            Syscall::Create { target, node } => {
+                // Only a syscall that is going to create something needs
+                // its result; whatever exists already keeps its owner
+                let creates = match self.file_request(pid, node) {
+                    Some((flags, _)) => flags & libc::O_CREAT != 0 && flags & libc::O_TMPFILE != libc::O_TMPFILE,
+                    None => true,
+                };
+                if creates && self.stat_target(pid, target).is_err() {
+                    Action::Inspect.into()
+                } else {
+                    Action::PassThrough.into()
+                }
+            }
+            // The last chance to hand down this identity before the
+            // children are reparented
+            Syscall::ExitGroup => Decision::new(Action::PassThrough, Effect::HandDown),
+            Syscall::Setuid(uid) => self.change_credentials(pid, |c| c.setuid(uid))?,
+            Syscall::Setgid(gid) => self.change_credentials(pid, |c| c.setgid(gid))?,
+            Syscall::Setreuid { ruid, euid } => self.change_credentials(pid, |c| c.setreuid(ruid, euid))?,
+            Syscall::Setregid { rgid, egid } => self.change_credentials(pid, |c| c.setregid(rgid, egid))?,
+            Syscall::Setresuid { ruid, euid, suid } => self.change_credentials(pid, |c| c.setresuid(ruid, euid, suid))?,
+            Syscall::Setresgid { rgid, egid, sgid } => self.change_credentials(pid, |c| c.setresgid(rgid, egid, sgid))?,
+            Syscall::Setfsuid(uid) => {
+                let (old, effect) = self.changed_credentials(pid, |c| c.setfsuid(uid));
+                Decision::new(Action::Emulate(old as i64), effect)
+            }
+            Syscall::Setfsgid(gid) => {
+                let (old, effect) = self.changed_credentials(pid, |c| c.setfsgid(gid));
+                Decision::new(Action::Emulate(old as i64), effect)
+            }
+            Syscall::Setgroups { size, list, narrow } => self.handle_setgroups(pid, size, list, narrow)?,
+            Syscall::Getuid { narrow } => Action::Emulate(id_return(self.peek_credentials(pid).current_uid, narrow)).into(),
+            Syscall::Geteuid { narrow } => Action::Emulate(id_return(self.peek_credentials(pid).effective_uid, narrow)).into(),
+            Syscall::Getgid { narrow } => Action::Emulate(id_return(self.peek_credentials(pid).current_gid, narrow)).into(),
+            Syscall::Getegid { narrow } => Action::Emulate(id_return(self.peek_credentials(pid).effective_gid, narrow)).into(),
+            Syscall::Getresuid { ptrs, narrow } => {
+                let creds = self.peek_credentials(pid);
+                write_ids(ptrs, [creds.current_uid, creds.effective_uid, creds.saved_uid], narrow)
+            }
+            Syscall::Getresgid { ptrs, narrow } => {
+                let creds = self.peek_credentials(pid);
+                write_ids(ptrs, [creds.current_gid, creds.effective_gid, creds.saved_gid], narrow)
+            }
+            Syscall::Getgroups { size, list, narrow } => self.handle_getgroups(pid, size, list, narrow),
+            Syscall::Capget { header, data } => self.handle_capget(pid, header, data),
+            Syscall::Capset { header, data } => self.handle_capset(pid, header, data)?,
+            // Options other than the capability ones are not ours to answer
+            Syscall::Prctl { option, args } => match self.changed_credentials(pid, |c| c.prctl(option, args)) {
+                (Some(Ok(result)), effect) => Decision::new(Action::Emulate(result), effect),
+                (Some(Err(errno)), _) => Action::Emulate(errno_return(errno as i32)).into(),
+                (None, _) => Action::PassThrough.into(),
+            },
+        };
+        
+        Ok(decision)
+    }
+    
+    /// Make the changes of a decision and return what the syscall should
+    /// do. The ptrace backend applies a decision right away, since its
+    /// tracee stays stopped in between
+    pub(crate) fn apply(&self, pid: Pid, decision: Decision) -> Result<Action> {
+        match decision.effect {
+            Effect::None => {}
+            Effect::Metadata(path, meta) => self.state_manager.set_metadata(path, meta)?,
+            Effect::Credentials(creds) => self.update_credentials(pid, |c| *c = creds),
+            Effect::HandDown => {
+                let creds = self.credentials(pid);
+                hand_down(&mut self.credentials.lock().unwrap(), pid, &creds);
+            }
+            Effect::Write(writes) => {
+                let memory = TraceeMemory::new(pid);
+                if writes.iter().any(|(addr, bytes)| memory.write(*addr, bytes).is_err()) {
+                    return Ok(Action::Emulate(errno_return(libc::EFAULT)));
+                }
+            }
+        }
+        Ok(decision.action)
+    }
+    
+    fn chowned(&self, pid: Pid, target: Target, real: &Metadata, uid: u32, gid: u32) -> Result<Effect> {
+        let path = self.target_path(pid, target)?;
+        let mut meta = self.fake_metadata(&path, real);
+        meta.chown(uid, gid, real.is_dir());
+        Ok(Effect::Metadata(path, meta))
+    }
+    
+    fn chmodded(&self, pid: Pid, target: Target, real: &Metadata, mode: u32) -> Result<Effect> {
+        let path = self.target_path(pid, target)?;
+        let mut meta = self.fake_metadata(&path, real);
+        meta.mode = mode & 0o7777;
+        Ok(Effect::Metadata(path, meta))
+    }
+    
+    /// Rewrite the buffer filled in by a successful stat with the fake
+    /// metadata of its file
+    pub(crate) fn overlay_stat(&self, pid: Pid, target: Target, buf: u64, layout: &StatLayout) -> Result<()> {
+        let path = self.target_path(pid, target)?;
+        let meta = {
+            let state = self.state_manager.get_state();
+            let state = state.lock().unwrap();
+            match state.get_metadata(&path) {
+                Some(meta) => meta.clone(),
+                None => return Ok(()),
+            }
+        };
+        
+        let memory = TraceeMemory::new(pid);
+        let mut bytes = vec![0u8; layout.prefix_len()];
+        memory.read(buf, &mut bytes)?;
+        stat::overlay(layout, &mut bytes, &meta);
+        memory.write(buf, &bytes)
+    }
+    
+    /// The fake metadata recorded for a path, or the file's real metadata
+    /// if it has not been touched yet
+    fn fake_metadata(&self, path: &Path, real: &Metadata) -> FakeMetadata {
+        let state = self.state_manager.get_state();
+        let state = state.lock().unwrap();
+        state.get_metadata(&path.to_path_buf()).cloned().unwrap_or_else(|| FakeMetadata::from(real))
+    }
+    
+    /// The flags and mode of an open that may create a regular file
+    fn file_request(&self, pid: Pid, node: NewNode) -> Option<(i32, u32)> {
+        match node {
+            NewNode::File { flags, mode } => Some((flags, mode)),
+            NewNode::FileHow(how) => {
+                let mut bytes = [0u8; 16];
+                TraceeMemory::new(pid).read(how, &mut bytes).ok()?;
+                let flags = u64::from_ne_bytes(bytes[..8].try_into().unwrap());
+                let mode = u64::from_ne_bytes(bytes[8..].try_into().unwrap());
+                Some((flags as i32, mode as u32))
+            }
+            _ => None,
+        }
+    }
+    
+    /// Give a file a successful creating syscall made the owner and mode
+    /// it would have had if the fake identity had created it: the creator's
+    /// filesystem ids, or the group of a set-group-ID parent directory, and
+    /// the requested mode less the umask
+    pub(crate) fn record_creation(&self, pid: Pid, target: Target, node: NewNode) -> Result<()> {
+        let path = self.target_path(pid, target)?;
+        let real = std::fs::symlink_metadata(&path)?;
+        let creds = self.credentials(pid);
+        let umask = process_umask(pid);
+        
+        let parent = path
+            .parent()
+            .and_then(|parent| std::fs::metadata(parent).ok().map(|real| self.fake_metadata(parent, &real)));
+        let inherit_gid = parent.as_ref().filter(|parent| parent.mode & libc::S_ISGID != 0).map(|parent| parent.gid);
+        let owned = |mode: u32| FakeMetadata {
+            uid: creds.fs_uid,
+            gid: inherit_gid.unwrap_or(creds.fs_gid),
+            mode,
+            capabilities: FileCaps::default(),
+        };
+        
+        let meta = match node {
+            NewNode::Directory(mode) => {
+                let mode = mode & (0o777 | libc::S_ISVTX) & !umask;
+                owned(if inherit_gid.is_some() { mode | libc::S_ISGID } else { mode })
+            }
+            NewNode::Device(mode) => owned(mode & 0o7777 & !umask),
+            NewNode::Symlink => owned(0o777),
+            NewNode::Link(source) => {
+                let source = self.target_path(pid, source)?;
+                self.fake_metadata(&source, &real)
+            }
+            NewNode::File { .. } | NewNode::FileHow(_) => {
+                let (_, mode) = self.file_request(pid, node).unwrap_or((0, 0o666));
+                owned(mode & 0o7777 & !umask)
+            }
+        };
+        
+        // A file that looks the same for real needs no entry, unless a stale
+        // one is left from a file that used to have this name
+        let known = self.state_manager.get_state().lock().unwrap().get_metadata(&path).is_some();
+        let unchanged = meta.uid == real.uid() && meta.gid == real.gid() && meta.mode == real.mode() & 0o7777 && meta.capabilities.is_empty();
+        if known || !unchanged {
+            self.state_manager.set_metadata(path, meta)?;
+        }
+        Ok(())
+    }
+    
+    /// Whether an access/faccessat the kernel refused with EACCES would
+    /// have succeeded for the fake identity
+    pub(crate) fn access_granted(&self, pid: Pid, target: Target, mode: i32, flags: i32) -> Result<bool> {
+        let creds = self.credentials(pid);
+        let uid = if flags & libc::AT_EACCESS != 0 {
+            creds.effective_uid
+        } else {
+            creds.current_uid
+        };
+        if uid != 0 {
+            return Ok(false);
+        }
+        
+        if mode & libc::X_OK != 0 {
+            let path = self.target_path(pid, target)?;
+            return Ok(root_may_execute(&self.state_manager, &path));
+        }
+        Ok(true)
+    }
+    
+    /// getgroups(2): a size of 0 asks for the number of groups only
+    fn handle_getgroups(&self, pid: Pid, size: i32, list: u64, narrow: bool) -> Decision {
+        let groups = self.peek_credentials(pid).groups;
+        if size < 0 || (size > 0 && (size as usize) < groups.len()) {
+            return Action::Emulate(errno_return(libc::EINVAL)).into();
+        }
+        let action = Action::Emulate(groups.len() as i64);
+        if size == 0 {
+            return action.into();
+        }
+        let bytes = groups.iter().flat_map(|gid| id_bytes(*gid, narrow)).collect();
+        Decision::new(action, Effect::Write(vec![(list, bytes)]))
+    }
+    
+    fn handle_setgroups(&self, pid: Pid, size: i32, list: u64, narrow: bool) -> Result<Decision> {
+        const NGROUPS_MAX: i32 = 65536;
+        if !(0..=NGROUPS_MAX).contains(&size) {
+            return Ok(Action::Emulate(errno_return(libc::EINVAL)).into());
+        }
+        
+        let width = if narrow { 2 } else { 4 };
+        let mut bytes = vec![0u8; size as usize * width];
+        if TraceeMemory::new(pid).read(list, &mut bytes).is_err() {
+            return Ok(Action::Emulate(errno_return(libc::EFAULT)).into());
+        }
+        let groups = if narrow {
+            bytes
+                .chunks_exact(2)
+                .map(|c| match u16::from_ne_bytes([c[0], c[1]]) {
+                    u16::MAX => u32::MAX,
+                    gid => gid as u32,
+                })
+                .collect()
+        } else {
+            bytes
+                .chunks_exact(4)
+                .map(|c| u32::from_ne_bytes([c[0], c[1], c[2], c[3]]))
+                .collect()
+        };
+        
+        self.change_credentials(pid, |c| c.setgroups(groups))
+    }
+    
+    /// Read a capget/capset header, the version and the thread it is about.
+    /// An unknown version gets the kernel's preferred one written back, so
+    /// that libcap can probe for it
+    fn read_cap_header(&self, pid: Pid, header: u64) -> std::result::Result<(usize, i32), Decision> {
+        let mut bytes = [0u8; 8];
+        if TraceeMemory::new(pid).read(header, &mut bytes).is_err() {
+            return Err(Action::Emulate(errno_return(libc::EFAULT)).into());
+        }
+        let version = u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        let target = i32::from_ne_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+        match caps::version_words(version) {
+            Some(words) => Ok((words, target)),
+            None => {
+                let version = caps::VERSION_3.to_ne_bytes().to_vec();
+                Err(Decision::new(Action::Emulate(errno_return(libc::EINVAL)), Effect::Write(vec![(header, version)])))
+            }
+        }
+    }
+    
+    /// capget(2) of the caller or of another thread with a fake identity;
+    /// the kernel answers for threads we know nothing about
+    fn handle_capget(&self, pid: Pid, header: u64, data: u64) -> Decision {
+        let (words, target) = match self.read_cap_header(pid, header) {
+            Ok(header) => header,
+            Err(decision) => return decision,
+        };
+        if target < 0 {
+            return Action::Emulate(errno_return(libc::EINVAL)).into();
+        }
+        let sets = if target == 0 || target == pid.as_raw() {
+            self.peek_credentials(pid).capabilities
+        } else {
+            match self.credentials.lock().unwrap().get(&Pid::from_raw(target)) {
+                Some(creds) => creds.capabilities,
+                None => return Action::PassThrough.into(),
+            }
+        };
+        
+        // A null data pointer only asks whether the version is supported
+        if data == 0 {
+            return Action::Emulate(0).into();
+        }
+        let bytes = sets.user_data(words).iter().flat_map(|word| word.to_ne_bytes()).collect();
+        Decision::new(Action::Emulate(0), Effect::Write(vec![(data, bytes)]))
+    }
+    
+    /// capset(2), which may only change the calling thread
+    fn handle_capset(&self, pid: Pid, header: u64, data: u64) -> Result<Decision> {
+        let (words, target) = match self.read_cap_header(pid, header) {
+            Ok(header) => header,
+            Err(decision) => return Ok(decision),
+        };
+        if target != 0 && target != pid.as_raw() {
+            return Ok(Action::Emulate(errno_return(libc::EPERM)).into());
+        }
+        
+        let mut bytes = vec![0u8; words * 3 * 4];
+        if TraceeMemory::new(pid).read(data, &mut bytes).is_err() {
+            return Ok(Action::Emulate(errno_return(libc::EFAULT)).into());
+        }
+        let data: Vec<u32> = bytes
+            .chunks_exact(4)
+            .map(|c| u32::from_ne_bytes([c[0], c[1], c[2], c[3]]))
+            .collect();
+        self.change_credentials(pid, |c| c.capabilities.capset(&data))
+    }
+    
+    fn is_root(&self, pid: Pid) -> bool {
+        self.peek_credentials(pid).is_root()
+    }
+    
+    /// The fake identity of a thread. One seen for the first time inherits
+    /// the identity of its thread group, or else of its parent process,
+    /// falling back to the identity the session started with
+    pub(crate) fn credentials(&self, pid: Pid) -> Credentials {
+        self.update_credentials(pid, |c| c.clone())
+    }
+    
+    /// The fake identity of a thread, like `credentials`, but without
+    /// writing down the one a thread seen for the first time inherits
+    fn peek_credentials(&self, pid: Pid) -> Credentials {
+        let credentials = self.credentials.lock().unwrap();
+        credentials.get(&pid).cloned().unwrap_or_else(|| self.inherited(&credentials, pid))
+    }
+    
+    fn inherited(&self, credentials: &HashMap<Pid, Credentials>, pid: Pid) -> Credentials {
+        process_ancestry(pid)
+            .into_iter()
+            .find_map(|ancestor| credentials.get(&ancestor).cloned())
+            .unwrap_or_else(|| self.state_manager.get_state().lock().unwrap().credentials.clone())
+    }
+    
+    /// Work out a thread's new fake identity on a copy of its current one,
+    /// with the effect that gives it the copy if anything changed
+    fn changed_credentials<T>(&self, pid: Pid, f: impl FnOnce(&mut Credentials) -> T) -> (T, Effect) {
+        let before = self.peek_credentials(pid);
+        let mut after = before.clone();
+        let result = f(&mut after);
+        let effect = if after != before { Effect::Credentials(after) } else { Effect::None };
+        (result, effect)
+    }
+    
+    /// Emulate a credential change, which changes nothing when it fails
+    fn change_credentials(&self, pid: Pid, f: impl FnOnce(&mut Credentials) -> Result<()>) -> Result<Decision> {
+        let (result, effect) = self.changed_credentials(pid, f);
+        let effect = if result.is_ok() { effect } else { Effect::None };
+        Ok(Decision::new(Action::Emulate(credential_return(result)?), effect))
+    }
+    
+    fn update_credentials<T>(&self, pid: Pid, f: impl FnOnce(&mut Credentials) -> T) -> T {
+        let mut credentials = self.credentials.lock().unwrap();
+        if !credentials.contains_key(&pid) {
+            let inherited = self.inherited(&credentials, pid);
+            credentials.insert(pid, inherited);
+        }
+        
+        let before = credentials[&pid].clone();
+        let result = f(credentials.get_mut(&pid).unwrap());
+        if credentials[&pid] != before {
+            hand_down(&mut credentials, pid, &before);
+        }
+        result
+    }
+    
+    /// Give a new thread or process a copy of its creator's identity
+    pub(crate) fn fork(&self, parent: Pid, child: Pid) {
+        let creds = self.credentials(parent);
+        self.credentials.lock().unwrap().insert(child, creds);
+    }
+    
+    /// Apply a successful execve to the identity of the thread that made
+    /// it, which may have taken over the pid of its thread group leader.
+    /// The program it now runs decides through its fake metadata
+    pub(crate) fn exec(&self, former: Pid, pid: Pid) {
+        {
+            let mut credentials = self.credentials.lock().unwrap();
+            if let Some(creds) = credentials.remove(&former) {
+                credentials.insert(pid, creds);
+            }
+        }
+        
+        let path = match std::fs::read_link(format!("/proc/{}/exe", pid)) {
+            Ok(path) => paths::canonicalize(&path, true),
+            Err(_) => return,
+        };
+        let meta = match std::fs::metadata(&path) {
+            Ok(real) => self.fake_metadata(&path, &real),
+            Err(_) => return,
+        };
+        self.update_credentials(pid, |c| c.exec(&meta));
+    }
+    
+    pub(crate) fn exit(&self, pid: Pid) {
+        self.credentials.lock().unwrap().remove(&pid);
+    }
+    
+    /// Drop the identities of threads that are gone, for a backend that is
+    /// not told about exits
+    pub(crate) fn forget_exited(&self) {
+        self.credentials
+            .lock()
+            .unwrap()
+            .retain(|pid, _| Path::new(&format!("/proc/{}", pid)).exists());
+    }
+    
+    /// Stat the file an emulated syscall operates on, looking it up through
+    /// the tracee's /proc view so relative paths and descriptors resolve
+    /// the way the kernel would resolve them
+    fn stat_target(&self, pid: Pid, target: Target) -> std::result::Result<Metadata, Errno> {
+        let (path, follow) = match target {
+            Target::Fd(fd) => (PathBuf::from(format!("/proc/{}/fd/{}", pid, fd)), true),
+            Target::Path { dirfd, ptr, flags } => {
+                let path = TraceeMemory::new(pid)
+                    .read_path(ptr)
+                    .map(PathBuf::from)
+                    .map_err(|_| Errno::EFAULT)?;
+                let follow = flags & libc::AT_SYMLINK_NOFOLLOW == 0;
+                if path.as_os_str().is_empty() {
+                    if flags & libc::AT_EMPTY_PATH == 0 {
+                        return Err(Errno::ENOENT);
+                    }
+                    (PathBuf::from(format!("/proc/{}/fd/{}", pid, dirfd)), true)
+                } else if path.is_absolute() {
+                    (path, follow)
+                } else if dirfd == libc::AT_FDCWD {
+                    (PathBuf::from(format!("/proc/{}/cwd", pid)).join(path), follow)
+                } else {
+                    (PathBuf::from(format!("/proc/{}/fd/{}", pid, dirfd)).join(path), follow)
+                }
+            }
+        };
+        
+        let result = if follow {
+            std::fs::metadata(&path)
+        } else {
+            std::fs::symlink_metadata(&path)
+        };
+        result.map_err(|e| Errno::from_i32(e.raw_os_error().unwrap_or(libc::ENOENT)))
+    }
+    
+    /// Resolve the file an emulated syscall operates on to its canonical
+    /// state key, anchoring relative paths at the tracee's working
+    /// directory or `dirfd`
+    pub(crate) fn target_path(&self, pid: Pid, target: Target) -> Result<PathBuf> {
+        match target {
+            Target::Fd(fd) => self.fd_path(pid, fd),
+            Target::Path { dirfd, ptr, flags } => {
+                let path = PathBuf::from(TraceeMemory::new(pid).read_path(ptr)?);
+                if path.as_os_str().is_empty() && flags & libc::AT_EMPTY_PATH != 0 {
+                    return self.fd_path(pid, dirfd);
+                }
+                
+                let path = if path.is_absolute() {
+                    path
+                } else if dirfd == libc::AT_FDCWD {
+                    self.cwd(pid)?.join(path)
+                } else {
+                    self.fd_path(pid, dirfd)?.join(path)
+                };
+                Ok(paths::canonicalize(&path, flags & libc::AT_SYMLINK_NOFOLLOW == 0))
+            }
+        }
+    }
+    
+    /// The tracee's current working directory
+    fn cwd(&self, pid: Pid) -> Result<PathBuf> {
+        std::fs::read_link(format!("/proc/{}/cwd", pid))
+            .map_err(|e| MinSukiError::Syscall(format!("cannot resolve cwd of {}: {}", pid, e)))
+    }
+    
+    /// Resolve a tracee file descriptor to the path it refers to
+    fn fd_path(&self, pid: Pid, fd: i32) -> Result<PathBuf> {
+        std::fs::read_link(format!("/proc/{}/fd/{}", pid, fd))
+            .map_err(|e| MinSukiError::Syscall(format!("cannot resolve fd {} of {}: {}", fd, pid, e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_child_keeps_identity_it_was_forked_with() {
+        let dir = TempDir::new().unwrap();
+        let state = StateManager::new(dir.path().join("state").to_str().unwrap()).unwrap();
+        let emulator = Emulator::new(state);
+        let parent = Pid::this();
+        
+        // The child makes no intercepted syscall before its parent drops
+        // root, so it is first seen afterwards
+        let mut before = std::process::Command::new("sleep").arg("10").spawn().unwrap();
+        let decision = emulator.decide(parent, Syscall::Setuid(1000)).unwrap();
+        assert_eq!(emulator.apply(parent, decision).unwrap(), Action::Emulate(0));
+        let mut after = std::process::Command::new("sleep").arg("10").spawn().unwrap();
+        
+        assert_eq!(emulator.credentials(Pid::from_raw(before.id() as i32)).effective_uid, 0);
+        assert_eq!(emulator.credentials(Pid::from_raw(after.id() as i32)).effective_uid, 1000);
+        before.kill().unwrap();
+        after.kill().unwrap();
+        before.wait().unwrap();
+        after.wait().unwrap();
+    }
+    
+    #[test]
+    fn test_decision_changes_nothing_until_applied() {
+        let dir = TempDir::new().unwrap();
+        let state = StateManager::new(dir.path().join("state").to_str().unwrap()).unwrap();
+        let emulator = Emulator::new(state);
+        let pid = Pid::this();
+        
+        let decision = emulator.decide(pid, Syscall::Setuid(1000)).unwrap();
+        assert_eq!(emulator.credentials(pid).effective_uid, 0);
+        assert_eq!(emulator.apply(pid, decision).unwrap(), Action::Emulate(0));
+        assert_eq!(emulator.credentials(pid).effective_uid, 1000);
+    }
+}
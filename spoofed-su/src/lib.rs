@@ -1,8 +1,19 @@
 pub mod types;
 pub mod state;
-pub mod preload;
+pub mod caps;
+pub mod emulate;
+pub mod memory;
+pub mod paths;
 pub mod ptrace;
+pub mod report;
+pub mod seccomp;
+pub mod stat;
+pub mod syscalls;
+pub mod trace;
+pub mod userdb;
 
-pub use types::{Config, FakeState, FakeMetadata, MinSukiError, Result};
+pub use types::{Config, Credentials, FakeState, FakeMetadata, MinSukiError, Result};
 pub use state::StateManager;
-pub use ptrace::PtraceInterceptor;
\ No newline at end of file
+pub use ptrace::PtraceInterceptor;
+pub use seccomp::SeccompInterceptor;
+pub use trace::TraceLog;
\ No newline at end of file
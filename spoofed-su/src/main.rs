@@ -1,6 +1,8 @@
 use clap::{Parser, Subcommand};
-use minsuki::{Config, PtraceInterceptor, StateManager};
-use std::path::PathBuf;
+use minsuki::caps::FileCaps;
+use minsuki::types::{CREDENTIALS_ENV, ROOTFS_ENV};
+use minsuki::{paths, PtraceInterceptor, SeccompInterceptor, StateManager, TraceLog};
+use std::path::{Path, PathBuf};
 use std::process;
 
 #[derive(Parser)]
@@ -23,6 +25,52 @@ enum Commands {
         #[arg(short, long, default_value = "/tmp/minsuki.state")]
         state: String,
         
+        /// Write one JSON line per intercepted syscall to this file
+        #[arg(long)]
+        trace: Option<String>,
+        
+        /// Summarize privileged operations when the command exits, on
+        /// stderr or in the file given as --report=FILE
+        #[arg(long, value_name = "FILE", num_args = 0..=1, require_equals = true, default_missing_value = "-")]
+        report: Option<String>,
+        
+        /// Verbose logging
+        #[arg(short, long)]
+        verbose: bool,
+    },
+    
+    /// Apply fake root privileges to a running process (ptrace mode)
+    Attach {
+        /// Process ID to attach to
+        pid: i32,
+        
+        /// State file path
+        #[arg(short, long, default_value = "/tmp/minsuki.state")]
+        state: String,
+        
+        /// Write one JSON line per intercepted syscall to this file
+        #[arg(long)]
+        trace: Option<String>,
+        
+        /// Verbose logging
+        #[arg(short, long)]
+        verbose: bool,
+    },
+    
+    /// Run a command with fake root privileges (seccomp user-notification mode)
+    Seccomp {
+        /// The command to execute
+        #[arg(required = true)]
+        command: Vec<String>,
+        
+        /// State file path
+        #[arg(short, long, default_value = "/tmp/minsuki.state")]
+        state: String,
+        
+        /// Write one JSON line per intercepted syscall to this file
+        #[arg(long)]
+        trace: Option<String>,
+        
         /// Verbose logging
         #[arg(short, long)]
         verbose: bool,
@@ -38,10 +86,19 @@ enum Commands {
         #[arg(short, long, default_value = "/tmp/minsuki.state")]
         state: String,
         
-        /// Path to libminsuki.so
+        /// Path to libminsuki_preload.so
         #[arg(short, long)]
         lib: Option<String>,
         
+        /// Answer user and group lookups from etc/passwd and etc/group
+        /// under this directory, such as envspoof's root filesystem
+        #[arg(long)]
+        rootfs: Option<String>,
+        
+        /// Write one JSON line per intercepted syscall to this file
+        #[arg(long)]
+        trace: Option<String>,
+        
         /// Verbose logging
         #[arg(short, long)]
         verbose: bool,
@@ -89,19 +146,41 @@ enum Commands {
         #[arg(short, long, default_value = "/tmp/minsuki.state")]
         state: String,
     },
+    
+    /// Manually set fake file capabilities, applied when the file is executed
+    Setcap {
+        /// Capabilities in cap_from_text syntax (e.g., cap_net_raw=ep), or
+        /// an empty string to remove them
+        capabilities: String,
+        
+        /// File path
+        path: String,
+        
+        /// State file path
+        #[arg(short, long, default_value = "/tmp/minsuki.state")]
+        state: String,
+    },
 }
 
 fn main() {
     let cli = Cli::parse();
     
     let result = match cli.command {
-        Commands::Run { command, state, verbose } => {
+        Commands::Run { command, state, trace, report, verbose } => {
+            setup_logging(verbose);
+            run_with_ptrace(command, &state, trace, report)
+        }
+        Commands::Attach { pid, state, trace, verbose } => {
+            setup_logging(verbose);
+            attach_with_ptrace(pid, &state, trace)
+        }
+        Commands::Seccomp { command, state, trace, verbose } => {
             setup_logging(verbose);
-            run_with_ptrace(command, &state)
+            run_with_seccomp(command, &state, trace)
         }
-        Commands::Preload { command, state, lib, verbose } => {
+        Commands::Preload { command, state, lib, rootfs, trace, verbose } => {
             setup_logging(verbose);
-            run_with_preload(command, &state, lib)
+            run_with_preload(command, &state, lib, rootfs, trace)
         }
         Commands::Status { state } => {
             show_status(&state)
@@ -115,6 +194,9 @@ fn main() {
         Commands::Chmod { path, mode, state } => {
             manual_chmod(&path, &mode, &state)
         }
+        Commands::Setcap { capabilities, path, state } => {
+            manual_setcap(&path, &capabilities, &state)
+        }
     };
     
     if let Err(e) = result {
@@ -128,38 +210,84 @@ fn setup_logging(verbose: bool) {
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(log_level)).init();
 }
 
-fn run_with_ptrace(command: Vec<String>, state_file: &str) -> Result<(), Box<dyn std::error::Error>> {
+fn run_with_ptrace(command: Vec<String>, state_file: &str, trace_file: Option<String>, report_file: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
     println!("🔒 MinSuki: Running with ptrace interception");
     println!("📦 Command: {}", command.join(" "));
     println!("💾 State file: {}", state_file);
     println!();
     
-    let interceptor = PtraceInterceptor::new(state_file)?;
+    let mut interceptor = PtraceInterceptor::new(state_file)?;
+    if let Some(trace_file) = trace_file {
+        interceptor = interceptor.with_trace(TraceLog::open(Path::new(&trace_file))?);
+    }
+    if report_file.is_some() {
+        interceptor = interceptor.with_report();
+    }
+    interceptor.run(&command)?;
+    
+    if let (Some(report_file), Some(report)) = (report_file, interceptor.report()) {
+        if report_file == "-" {
+            eprintln!();
+            eprint!("{}", report);
+        } else {
+            std::fs::write(&report_file, report)?;
+            println!("📋 Report written to {}", report_file);
+        }
+    }
+    
+    Ok(())
+}
+
+fn attach_with_ptrace(pid: i32, state_file: &str, trace_file: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+    println!("🔒 MinSuki: Attaching to process {} with ptrace interception", pid);
+    println!("💾 State file: {}", state_file);
+    println!("⏹️  Press Ctrl-C to detach");
+    println!();
+    
+    let mut interceptor = PtraceInterceptor::new(state_file)?;
+    if let Some(trace_file) = trace_file {
+        interceptor = interceptor.with_trace(TraceLog::open(Path::new(&trace_file))?);
+    }
+    interceptor.attach(nix::unistd::Pid::from_raw(pid))?;
+    
+    Ok(())
+}
+
+fn run_with_seccomp(command: Vec<String>, state_file: &str, trace_file: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+    println!("🔒 MinSuki: Running with seccomp interception");
+    println!("📦 Command: {}", command.join(" "));
+    println!("💾 State file: {}", state_file);
+    println!();
+    
+    let mut interceptor = SeccompInterceptor::new(state_file)?;
+    if let Some(trace_file) = trace_file {
+        interceptor = interceptor.with_trace(TraceLog::open(Path::new(&trace_file))?);
+    }
     interceptor.run(&command)?;
     
     Ok(())
 }
 
-fn run_with_preload(command: Vec<String>, state_file: &str, lib_path: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+fn run_with_preload(command: Vec<String>, state_file: &str, lib_path: Option<String>, rootfs: Option<String>, trace_file: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
     println!("🔒 MinSuki: Running with LD_PRELOAD interception");
     println!("📦 Command: {}", command.join(" "));
     println!("💾 State file: {}", state_file);
     
-    // Find libminsuki.so
+    // Find libminsuki_preload.so
     let lib = if let Some(path) = lib_path {
         path
     } else {
         // Try to find it in common locations
         let candidates = vec![
-            "./target/debug/libminsuki.so",
-            "./target/release/libminsuki.so",
-            "/usr/local/lib/libminsuki.so",
-            "/usr/lib/libminsuki.so",
+            "./target/debug/libminsuki_preload.so",
+            "./target/release/libminsuki_preload.so",
+            "/usr/local/lib/libminsuki_preload.so",
+            "/usr/lib/libminsuki_preload.so",
         ];
         
         candidates.into_iter()
             .find(|p| PathBuf::from(p).exists())
-            .ok_or("Could not find libminsuki.so. Please specify with --lib")?
+            .ok_or("Could not find libminsuki_preload.so. Please specify with --lib")?
             .to_string()
     };
     
@@ -169,6 +297,16 @@ fn run_with_preload(command: Vec<String>, state_file: &str, lib_path: Option<Str
     // Set environment variables
     std::env::set_var("LD_PRELOAD", &lib);
     std::env::set_var("MINSUKI_STATE", state_file);
+    // Start from the session's identity, not one inherited from an outer session
+    std::env::remove_var(CREDENTIALS_ENV);
+    match rootfs {
+        Some(rootfs) => std::env::set_var(ROOTFS_ENV, paths::absolute(Path::new(&rootfs))),
+        None => std::env::remove_var(ROOTFS_ENV),
+    }
+    if let Some(trace_file) = trace_file {
+        // Every preloaded process appends to the file, so resolve it now
+        std::env::set_var("MINSUKI_TRACE", paths::absolute(Path::new(&trace_file)));
+    }
     
     // Execute the command
     let status = process::Command::new(&command[0])
@@ -190,12 +328,22 @@ fn show_status(state_file: &str) -> Result<(), Box<dyn std::error::Error>> {
     println!("📊 MinSuki State Report");
     println!("========================");
     println!();
-    println!("Current UID: {}", state.current_uid);
-    println!("Current GID: {}", state.current_gid);
-    println!("Effective UID: {} ({})", state.effective_uid, if state.effective_uid == 0 { "root" } else { "user" });
-    println!("Effective GID: {} ({})", state.effective_gid, if state.effective_gid == 0 { "root" } else { "user" });
+    let creds = &state.credentials;
+    let caps = &creds.capabilities;
+    println!("Starting identity of every process:");
+    println!("  Current UID: {}", creds.current_uid);
+    println!("  Current GID: {}", creds.current_gid);
+    println!("  Effective UID: {} ({})", creds.effective_uid, if creds.effective_uid == 0 { "root" } else { "user" });
+    println!("  Effective GID: {} ({})", creds.effective_gid, if creds.effective_gid == 0 { "root" } else { "user" });
     println!();
-    println!("Capabilities: {}", state.capabilities.join(", "));
+    // Laid out like `capsh --print`
+    println!("Current: {}", caps);
+    println!("Bounding set ={}", caps.bounding);
+    println!("Ambient set ={}", caps.ambient);
+    println!("uid={} euid={} suid={} fsuid={}", creds.current_uid, creds.effective_uid, creds.saved_uid, creds.fs_uid);
+    println!("gid={} egid={} sgid={} fsgid={}", creds.current_gid, creds.effective_gid, creds.saved_gid, creds.fs_gid);
+    let groups: Vec<String> = creds.groups.iter().map(|g| g.to_string()).collect();
+    println!("groups={}", groups.join(","));
     println!();
     println!("Fake File Metadata ({} entries):", state.files.len());
     println!("----------------------------------");
@@ -203,6 +351,9 @@ fn show_status(state_file: &str) -> Result<(), Box<dyn std::error::Error>> {
     for (path, meta) in &state.files {
         println!("  {:?}", path.display());
         println!("    UID: {}, GID: {}, Mode: {:o}", meta.uid, meta.gid, meta.mode);
+        if !meta.capabilities.is_empty() {
+            println!("    Capabilities: {}", meta.capabilities);
+        }
     }
     
     Ok(())
@@ -220,7 +371,7 @@ fn clear_state(state_file: &str) -> Result<(), Box<dyn std::error::Error>> {
 
 fn manual_chown(path: &str, uid: u32, gid: u32, state_file: &str) -> Result<(), Box<dyn std::error::Error>> {
     let manager = StateManager::new(state_file)?;
-    manager.chown(PathBuf::from(path), uid, gid)?;
+    manager.chown(paths::canonicalize(Path::new(path), true), uid, gid)?;
     println!("✅ Set ownership of {} to {}:{}", path, uid, gid);
     Ok(())
 }
@@ -228,7 +379,15 @@ fn manual_chown(path: &str, uid: u32, gid: u32, state_file: &str) -> Result<(),
 fn manual_chmod(path: &str, mode_str: &str, state_file: &str) -> Result<(), Box<dyn std::error::Error>> {
     let mode = u32::from_str_radix(mode_str, 8)?;
     let manager = StateManager::new(state_file)?;
-    manager.chmod(PathBuf::from(path), mode)?;
+    manager.chmod(paths::canonicalize(Path::new(path), true), mode)?;
     println!("✅ Set permissions of {} to {:o}", path, mode);
     Ok(())
+}
+
+fn manual_setcap(path: &str, caps_str: &str, state_file: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let capabilities = FileCaps::from_text(caps_str)?;
+    let manager = StateManager::new(state_file)?;
+    manager.setcap(paths::canonicalize(Path::new(path), true), capabilities)?;
+    println!("✅ Set capabilities of {} to {}", path, capabilities);
+    Ok(())
 }
\ No newline at end of file
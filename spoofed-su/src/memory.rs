@@ -0,0 +1,206 @@
+use crate::types::{MinSukiError, Result};
+use nix::sys::ptrace;
+use nix::unistd::Pid;
+use std::ffi::OsString;
+use std::mem::size_of;
+use std::os::unix::ffi::OsStringExt;
+
+/// Size of the unit PTRACE_PEEKDATA and PTRACE_POKEDATA transfer
+const WORD: u64 = size_of::<libc::c_long>() as u64;
+
+/// Longest path the kernel accepts, terminating NUL included
+const PATH_MAX: usize = libc::PATH_MAX as usize;
+
+/// Access to the address space of a stopped tracee.
+///
+/// Bulk transfers go through process_vm_readv/process_vm_writev. When those
+/// are unavailable or refused (no kernel support, or a read-only mapping
+/// that only POKEDATA may write) the transfer falls back to word-at-a-time
+/// ptrace requests.
+pub struct TraceeMemory {
+    pid: Pid,
+}
+
+impl TraceeMemory {
+    pub fn new(pid: Pid) -> Self {
+        Self { pid }
+    }
+    
+    /// Fill `buf` from tracee memory starting at `addr`
+    pub fn read(&self, addr: u64, buf: &mut [u8]) -> Result<()> {
+        if buf.is_empty() || self.read_vm(addr, buf) {
+            return Ok(());
+        }
+        self.read_words(addr, buf)
+    }
+    
+    /// Copy `data` into tracee memory starting at `addr`
+    pub fn write(&self, addr: u64, data: &[u8]) -> Result<()> {
+        if data.is_empty() || self.write_vm(addr, data) {
+            return Ok(());
+        }
+        self.write_words(addr, data)
+    }
+    
+    /// Read a NUL-terminated path of at most PATH_MAX bytes
+    pub fn read_path(&self, addr: u64) -> Result<OsString> {
+        let mut path = Vec::new();
+        let mut addr = addr;
+        
+        // Never read across a page boundary in one go: the string may end
+        // just before an unmapped page
+        while path.len() < PATH_MAX {
+            let to_page_end = page_size() - addr % page_size();
+            let len = (to_page_end as usize).min(PATH_MAX - path.len());
+            let mut chunk = vec![0u8; len];
+            self.read(addr, &mut chunk)?;
+            
+            if let Some(nul) = chunk.iter().position(|&b| b == 0) {
+                path.extend_from_slice(&chunk[..nul]);
+                return Ok(OsString::from_vec(path));
+            }
+            path.extend_from_slice(&chunk);
+            addr += len as u64;
+        }
+        
+        // As the kernel does for a path with no NUL in its first PATH_MAX bytes
+        Err(MinSukiError::Io(std::io::Error::from_raw_os_error(libc::ENAMETOOLONG)))
+    }
+    
+    fn read_vm(&self, addr: u64, buf: &mut [u8]) -> bool {
+        let local = libc::iovec {
+            iov_base: buf.as_mut_ptr() as *mut libc::c_void,
+            iov_len: buf.len(),
+        };
+        let remote = libc::iovec {
+            iov_base: addr as *mut libc::c_void,
+            iov_len: buf.len(),
+        };
+        let n = unsafe { libc::process_vm_readv(self.pid.as_raw(), &local, 1, &remote, 1, 0) };
+        n == buf.len() as isize
+    }
+    
+    fn write_vm(&self, addr: u64, data: &[u8]) -> bool {
+        let local = libc::iovec {
+            iov_base: data.as_ptr() as *mut libc::c_void,
+            iov_len: data.len(),
+        };
+        let remote = libc::iovec {
+            iov_base: addr as *mut libc::c_void,
+            iov_len: data.len(),
+        };
+        let n = unsafe { libc::process_vm_writev(self.pid.as_raw(), &local, 1, &remote, 1, 0) };
+        n == data.len() as isize
+    }
+    
+    fn peek(&self, addr: u64) -> Result<[u8; WORD as usize]> {
+        ptrace::read(self.pid, addr as ptrace::AddressType)
+            .map(|word| word.to_ne_bytes())
+            .map_err(|e| MinSukiError::Ptrace(format!("peek at {:#x} failed: {}", addr, e)))
+    }
+    
+    fn poke(&self, addr: u64, word: [u8; WORD as usize]) -> Result<()> {
+        let word = libc::c_long::from_ne_bytes(word);
+        unsafe { ptrace::write(self.pid, addr as ptrace::AddressType, word as *mut libc::c_void) }
+            .map_err(|e| MinSukiError::Ptrace(format!("poke at {:#x} failed: {}", addr, e)))
+    }
+    
+    fn read_words(&self, addr: u64, buf: &mut [u8]) -> Result<()> {
+        let end = addr + buf.len() as u64;
+        let mut word_addr = addr - addr % WORD;
+        
+        while word_addr < end {
+            let word = self.peek(word_addr)?;
+            for (i, &byte) in word.iter().enumerate() {
+                let at = word_addr + i as u64;
+                if at >= addr && at < end {
+                    buf[(at - addr) as usize] = byte;
+                }
+            }
+            word_addr += WORD;
+        }
+        
+        Ok(())
+    }
+    
+    /// Write word by word, merging with the existing contents where `data`
+    /// only covers part of a word
+    fn write_words(&self, addr: u64, data: &[u8]) -> Result<()> {
+        let end = addr + data.len() as u64;
+        let mut word_addr = addr - addr % WORD;
+        
+        while word_addr < end {
+            let covered = word_addr >= addr && word_addr + WORD <= end;
+            let mut word = if covered { [0; WORD as usize] } else { self.peek(word_addr)? };
+            for (i, byte) in word.iter_mut().enumerate() {
+                let at = word_addr + i as u64;
+                if at >= addr && at < end {
+                    *byte = data[(at - addr) as usize];
+                }
+            }
+            self.poke(word_addr, word)?;
+            word_addr += WORD;
+        }
+        
+        Ok(())
+    }
+}
+
+fn page_size() -> u64 {
+    match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+        n if n > 0 => n as u64,
+        _ => 4096,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn own_memory() -> TraceeMemory {
+        TraceeMemory::new(Pid::this())
+    }
+    
+    #[test]
+    fn test_read_path_across_page_boundary() {
+        let page = page_size() as usize;
+        let mut buf = vec![0u8; 3 * page];
+        let base = buf.as_ptr() as usize;
+        let start = (base / page + 2) * page - 5 - base;
+        buf[start..start + 14].copy_from_slice(b"/tmp/straddle\0");
+        
+        let path = own_memory().read_path((base + start) as u64).unwrap();
+        assert_eq!(path, "/tmp/straddle");
+    }
+    
+    #[test]
+    fn test_read_path_keeps_raw_bytes() {
+        let buf = b"/tmp/\xff\xfe\0";
+        let path = own_memory().read_path(buf.as_ptr() as u64).unwrap();
+        assert_eq!(path.into_vec(), b"/tmp/\xff\xfe");
+    }
+    
+    #[test]
+    fn test_read_path_too_long() {
+        // The NUL right after PATH_MAX bytes is out of reach
+        let mut buf = vec![b'a'; PATH_MAX + 1];
+        buf[PATH_MAX] = 0;
+        match own_memory().read_path(buf.as_ptr() as u64) {
+            Err(MinSukiError::Io(e)) => assert_eq!(e.raw_os_error(), Some(libc::ENAMETOOLONG)),
+            other => panic!("expected ENAMETOOLONG, got {:?}", other),
+        }
+    }
+    
+    #[test]
+    fn test_write_read_round_trip() {
+        let target = vec![0u8; 64];
+        let addr = target.as_ptr() as u64 + 3;
+        let memory = own_memory();
+        memory.write(addr, b"fake root").unwrap();
+        
+        let mut back = [0u8; 11];
+        memory.read(addr - 1, &mut back).unwrap();
+        assert_eq!(&back, b"\0fake root\0");
+        assert_eq!(&target[3..12], b"fake root");
+    }
+}
@@ -0,0 +1,93 @@
+use std::path::{Component, Path, PathBuf};
+
+/// Turn a path into the key `FakeState` records it under.
+///
+/// Every directory component is resolved physically, so `..` after a
+/// symlink climbs out of the link's target just like the kernel does. The
+/// final component is only dereferenced when `follow` is set, which keeps
+/// `lchown` and `AT_SYMLINK_NOFOLLOW` operating on the link itself. Parts of
+/// the path that do not exist yet are normalised lexically.
+pub fn canonicalize(path: &Path, follow: bool) -> PathBuf {
+    let path = absolute(path);
+    
+    let (parent, name) = match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => (parent, name),
+        // The root directory, or a path ending in `..`
+        _ => return resolve(&path),
+    };
+    
+    let joined = resolve(parent).join(name);
+    if follow {
+        if let Ok(target) = std::fs::canonicalize(&joined) {
+            return target;
+        }
+    }
+    joined
+}
+
+/// Anchor a relative path at the current working directory
+pub fn absolute(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+    match std::env::current_dir() {
+        Ok(cwd) => cwd.join(path),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// Resolve an existing prefix of `path` through the filesystem and
+/// normalise whatever is left of it without touching the disk
+fn resolve(path: &Path) -> PathBuf {
+    if let Ok(resolved) = std::fs::canonicalize(path) {
+        return resolved;
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => resolve(parent).join(name),
+        _ => normalize(path),
+    }
+}
+
+/// Drop `.` components and fold `..` into its parent
+fn normalize(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                result.pop();
+            }
+            other => result.push(other),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_canonicalize_symlink_semantics() {
+        let dir = TempDir::new().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        std::fs::create_dir(root.join("real")).unwrap();
+        std::fs::write(root.join("real/file"), b"").unwrap();
+        std::os::unix::fs::symlink("real/file", root.join("link")).unwrap();
+        std::os::unix::fs::symlink("real", root.join("dir")).unwrap();
+        
+        assert_eq!(canonicalize(&root.join("link"), true), root.join("real/file"));
+        assert_eq!(canonicalize(&root.join("link"), false), root.join("link"));
+        assert_eq!(canonicalize(&root.join("dir/./file"), false), root.join("real/file"));
+        assert_eq!(canonicalize(&root.join("dir/../real/file"), true), root.join("real/file"));
+    }
+    
+    #[test]
+    fn test_canonicalize_missing_file() {
+        let dir = TempDir::new().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        
+        assert_eq!(canonicalize(&root.join("a/../b/./c"), true), root.join("b/c"));
+    }
+}
@@ -1,9 +1,5 @@
-// Android-compatible ptrace implementation
-// Complete ptrace.rs file
-
 use nix::sys::ptrace;
 use nix::unistd::Pid;
-use std::io;
 
 // Architecture-specific register handling
 #[cfg(target_arch = "aarch64")]
@@ -62,6 +58,46 @@ pub mod regs {
         }
         Ok(())
     }
+
+    pub fn syscall_number(regs: &user_regs_struct) -> i64 {
+        regs.regs[8] as i64
+    }
+
+    pub fn syscall_args(regs: &user_regs_struct) -> [u64; 6] {
+        [regs.regs[0], regs.regs[1], regs.regs[2], regs.regs[3], regs.regs[4], regs.regs[5]]
+    }
+
+    pub fn syscall_return(regs: &user_regs_struct) -> i64 {
+        regs.regs[0] as i64
+    }
+
+    pub fn set_return(regs: &mut user_regs_struct, value: i64) {
+        regs.regs[0] = value as u64;
+    }
+
+    /// Replace the syscall number at a syscall-enter stop with -1 so the
+    /// kernel skips it; arm64 only honours this through NT_ARM_SYSTEM_CALL
+    pub fn skip_syscall(pid: Pid) -> io::Result<()> {
+        const NT_ARM_SYSTEM_CALL: libc::c_int = 0x404;
+        let mut number: libc::c_int = -1;
+        let mut iov = libc::iovec {
+            iov_base: &mut number as *mut _ as *mut c_void,
+            iov_len: std::mem::size_of::<libc::c_int>(),
+        };
+        
+        unsafe {
+            if libc::ptrace(
+                libc::PTRACE_SETREGSET,
+                pid.as_raw(),
+                NT_ARM_SYSTEM_CALL,
+                &mut iov as *mut _ as *mut c_void,
+            ) == -1
+            {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
 }
 
 #[cfg(target_arch = "arm")]
@@ -106,6 +142,120 @@ pub mod regs {
         }
         Ok(())
     }
+
+    pub fn syscall_number(regs: &user_regs_struct) -> i64 {
+        regs.uregs[7] as i64
+    }
+
+    pub fn syscall_args(regs: &user_regs_struct) -> [u64; 6] {
+        let r = &regs.uregs;
+        [r[0] as u64, r[1] as u64, r[2] as u64, r[3] as u64, r[4] as u64, r[5] as u64]
+    }
+
+    pub fn syscall_return(regs: &user_regs_struct) -> i64 {
+        regs.uregs[0] as i32 as i64
+    }
+
+    pub fn set_return(regs: &mut user_regs_struct, value: i64) {
+        regs.uregs[0] = value as u32;
+    }
+
+    /// Replace the syscall number at a syscall-enter stop with -1 so the
+    /// kernel skips it; arm only honours this through PTRACE_SET_SYSCALL
+    pub fn skip_syscall(pid: Pid) -> io::Result<()> {
+        const PTRACE_SET_SYSCALL: libc::c_int = 23;
+        unsafe {
+            if libc::ptrace(PTRACE_SET_SYSCALL as _, pid.as_raw(), 0, -1) == -1 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_arch = "riscv64")]
+pub mod regs {
+    use libc::c_void;
+    use nix::unistd::Pid;
+    use std::io;
+
+    // RISC-V user_regs_struct: pc followed by x1..x31
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct user_regs_struct {
+        pub pc: u64,
+        pub x: [u64; 31],
+    }
+
+    // Indices into `x` (register xN lives at x[N - 1])
+    const A0: usize = 9;
+    const A7: usize = 16;
+
+    pub fn getregs(pid: Pid) -> io::Result<user_regs_struct> {
+        let mut regs: user_regs_struct = unsafe { std::mem::zeroed() };
+        let mut iov = libc::iovec {
+            iov_base: &mut regs as *mut _ as *mut c_void,
+            iov_len: std::mem::size_of::<user_regs_struct>(),
+        };
+        
+        unsafe {
+            if libc::ptrace(
+                libc::PTRACE_GETREGSET,
+                pid.as_raw(),
+                libc::NT_PRSTATUS,
+                &mut iov as *mut _ as *mut c_void,
+            ) == -1
+            {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(regs)
+    }
+
+    pub fn setregs(pid: Pid, regs: user_regs_struct) -> io::Result<()> {
+        let mut iov = libc::iovec {
+            iov_base: &regs as *const _ as *mut c_void,
+            iov_len: std::mem::size_of::<user_regs_struct>(),
+        };
+        
+        unsafe {
+            if libc::ptrace(
+                libc::PTRACE_SETREGSET,
+                pid.as_raw(),
+                libc::NT_PRSTATUS,
+                &mut iov as *mut _ as *mut c_void,
+            ) == -1
+            {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn syscall_number(regs: &user_regs_struct) -> i64 {
+        regs.x[A7] as i64
+    }
+
+    pub fn syscall_args(regs: &user_regs_struct) -> [u64; 6] {
+        let a = &regs.x[A0..A0 + 6];
+        [a[0], a[1], a[2], a[3], a[4], a[5]]
+    }
+
+    pub fn syscall_return(regs: &user_regs_struct) -> i64 {
+        regs.x[A0] as i64
+    }
+
+    pub fn set_return(regs: &mut user_regs_struct, value: i64) {
+        regs.x[A0] = value as u64;
+    }
+
+    /// Replace the syscall number at a syscall-enter stop with -1 so the
+    /// kernel skips it
+    pub fn skip_syscall(pid: Pid) -> io::Result<()> {
+        let mut regs = getregs(pid)?;
+        regs.x[A7] = u64::MAX;
+        setregs(pid, regs)
+    }
 }
 
 #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
@@ -122,87 +272,243 @@ pub mod regs {
     pub fn setregs(pid: Pid, regs: user_regs_struct) -> io::Result<()> {
         ptrace::setregs(pid, regs).map_err(|e| io::Error::from_raw_os_error(e as i32))
     }
+
+    #[cfg(target_arch = "x86_64")]
+    pub fn syscall_number(regs: &user_regs_struct) -> i64 {
+        regs.orig_rax as i64
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    pub fn syscall_args(regs: &user_regs_struct) -> [u64; 6] {
+        [regs.rdi, regs.rsi, regs.rdx, regs.r10, regs.r8, regs.r9]
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    pub fn syscall_return(regs: &user_regs_struct) -> i64 {
+        regs.rax as i64
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    pub fn set_return(regs: &mut user_regs_struct, value: i64) {
+        regs.rax = value as u64;
+    }
+
+    #[cfg(target_arch = "x86")]
+    pub fn syscall_number(regs: &user_regs_struct) -> i64 {
+        regs.orig_eax as i64
+    }
+
+    #[cfg(target_arch = "x86")]
+    pub fn syscall_args(regs: &user_regs_struct) -> [u64; 6] {
+        let arg = |r: libc::c_long| r as u32 as u64;
+        [arg(regs.ebx), arg(regs.ecx), arg(regs.edx), arg(regs.esi), arg(regs.edi), arg(regs.ebp)]
+    }
+
+    #[cfg(target_arch = "x86")]
+    pub fn syscall_return(regs: &user_regs_struct) -> i64 {
+        regs.eax as i64
+    }
+
+    #[cfg(target_arch = "x86")]
+    pub fn set_return(regs: &mut user_regs_struct, value: i64) {
+        regs.eax = value as libc::c_long;
+    }
+
+    /// Replace the syscall number at a syscall-enter stop with -1 so the
+    /// kernel skips it
+    pub fn skip_syscall(pid: Pid) -> io::Result<()> {
+        let mut regs = getregs(pid)?;
+        #[cfg(target_arch = "x86_64")]
+        { regs.orig_rax = u64::MAX; }
+        #[cfg(target_arch = "x86")]
+        { regs.orig_eax = -1; }
+        setregs(pid, regs)
+    }
 }
 
+use crate::emulate::{Action, Emulator, Syscall};
+use crate::report::Report;
+use crate::seccomp;
 use crate::state::StateManager;
+use crate::syscalls::Sysno;
+use crate::trace::{Disposition, TraceEvent, TraceLog};
 use crate::types::{MinSukiError, Result};
-use nix::sys::signal::Signal;
-use nix::sys::wait::{waitpid, WaitStatus};
-use nix::unistd::{fork, ForkResult, Pid};
-use std::path::PathBuf;
+use nix::errno::Errno;
+use nix::sys::signal::{self, SaFlags, SigAction, SigHandler, SigSet, Signal};
+use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
+use nix::unistd::{fork, getpgid, getpgrp, ForkResult};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
 
-/// Syscall numbers - architecture specific
-#[cfg(target_arch = "aarch64")]
-mod syscall {
-    pub const CHOWN: i64 = 53;
-    pub const FCHOWN: i64 = 55;
-    pub const LCHOWN: i64 = 16;
-    pub const CHMOD: i64 = 15;
-    pub const FCHMOD: i64 = 52;
-    pub const SETUID: i64 = 146;
-    pub const SETGID: i64 = 144;
-    pub const GETUID: i64 = 174;
-    pub const GETEUID: i64 = 175;
-    pub const GETGID: i64 = 176;
-    pub const GETEGID: i64 = 177;
+/// Per-tracee bookkeeping for the tracing loop
+#[derive(Debug, Default)]
+struct Tracee {
+    /// True between the syscall-enter and syscall-exit stops; only tracked
+    /// for attached processes, which stop on every syscall
+    in_syscall: bool,
+    
+    /// The intercepted syscall in flight, if any, awaiting its exit stop
+    pending: Option<Pending>,
 }
 
-#[cfg(target_arch = "arm")]
-mod syscall {
-    pub const CHOWN: i64 = 182;
-    pub const FCHOWN: i64 = 207;
-    pub const LCHOWN: i64 = 16;
-    pub const CHMOD: i64 = 15;
-    pub const FCHMOD: i64 = 94;
-    pub const SETUID: i64 = 213;
-    pub const SETGID: i64 = 214;
-    pub const GETUID: i64 = 199;
-    pub const GETEUID: i64 = 201;
-    pub const GETGID: i64 = 200;
-    pub const GETEGID: i64 = 202;
+/// An intercepted syscall waiting for its exit stop
+#[derive(Debug, Clone, Copy)]
+struct Pending {
+    sysno: Sysno,
+    syscall: Syscall,
+    action: Action,
 }
 
-#[cfg(target_arch = "x86_64")]
-mod syscall {
-    pub const CHOWN: i64 = 92;
-    pub const FCHOWN: i64 = 93;
-    pub const LCHOWN: i64 = 94;
-    pub const CHMOD: i64 = 90;
-    pub const FCHMOD: i64 = 91;
-    pub const SETUID: i64 = 105;
-    pub const SETGID: i64 = 106;
-    pub const GETUID: i64 = 102;
-    pub const GETEUID: i64 = 107;
-    pub const GETGID: i64 = 104;
-    pub const GETEGID: i64 = 108;
+/// How the traced processes came under minsuki
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// A command we started: it carries our seccomp filter, so only
+    /// intercepted syscalls stop, and it dies with us
+    Run,
+    
+    /// A process that was already running: it stops on every syscall, and
+    /// is detached rather than killed when we are interrupted
+    Attach,
 }
 
-#[cfg(target_arch = "x86")]
-mod syscall {
-    pub const CHOWN: i64 = 182;
-    pub const FCHOWN: i64 = 95;
-    pub const LCHOWN: i64 = 16;
-    pub const CHMOD: i64 = 15;
-    pub const FCHMOD: i64 = 94;
-    pub const SETUID: i64 = 213;
-    pub const SETGID: i64 = 214;
-    pub const GETUID: i64 = 199;
-    pub const GETEUID: i64 = 201;
-    pub const GETGID: i64 = 200;
-    pub const GETEGID: i64 = 202;
+/// Signals sent to minsuki itself that should reach the traced command,
+/// or that end an attach session
+const FORWARDED_SIGNALS: [Signal; 3] = [Signal::SIGINT, Signal::SIGTERM, Signal::SIGHUP];
+
+/// Last of FORWARDED_SIGNALS received, or 0; set by `on_signal`
+static RECEIVED_SIGNAL: AtomicI32 = AtomicI32::new(0);
+
+/// Whether the terminal sent RECEIVED_SIGNAL, rather than kill(2)
+static FROM_TERMINAL: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_signal(signal: libc::c_int, info: *mut libc::siginfo_t, _: *mut libc::c_void) {
+    FROM_TERMINAL.store(unsafe { (*info).si_code } > 0, Ordering::Relaxed);
+    RECEIVED_SIGNAL.store(signal, Ordering::Relaxed);
+}
+
+/// State of one tracing session
+struct Session {
+    mode: Mode,
+    
+    /// The process whose exit is reported and that forwarded signals go to
+    leader: Pid,
+    
+    tracees: HashMap<Pid, Tracee>,
+    
+    /// Set once an attach session is interrupted; tracees are then detached
+    /// at their next stop
+    detaching: bool,
+}
+
+impl Session {
+    fn new(mode: Mode, leader: Pid, tids: &[Pid]) -> Self {
+        let tracees = tids.iter().map(|tid| (*tid, Tracee::default())).collect();
+        Self { mode, leader, tracees, detaching: false }
+    }
+    
+    /// Resume a stopped tracee. Attached processes stop at every syscall;
+    /// spawned ones only again at the exit of an intercepted syscall that
+    /// is waiting for it. While detaching, idle tracees are let go instead
+    fn resume(&mut self, pid: Pid, signal: Option<Signal>) {
+        let tracee = self.tracees.entry(pid).or_default();
+        
+        let result = if self.detaching && tracee.pending.is_none() {
+            self.tracees.remove(&pid);
+            log::debug!("Detaching from {}", pid);
+            ptrace::detach(pid, signal)
+        } else if self.mode == Mode::Attach || tracee.pending.is_some() {
+            ptrace::syscall(pid, signal)
+        } else {
+            ptrace::cont(pid, signal)
+        };
+        
+        // ESRCH means the tracee was killed while stopped; its exit is
+        // still reported through waitpid, so there is nothing to do here
+        if let Err(e) = result {
+            log::debug!("Failed to resume tracee {}: {}", pid, e);
+        }
+    }
+    
+    /// React to a signal sent to minsuki itself
+    fn handle_signal(&mut self, signal: i32, from_terminal: bool) {
+        let signal = match Signal::try_from(signal) {
+            Ok(signal) => signal,
+            Err(_) => return,
+        };
+        
+        match self.mode {
+            // Signals from the terminal already went to the whole
+            // foreground process group, the traced command included
+            Mode::Run if from_terminal => {}
+            Mode::Run => Self::forward_signal(self.leader, signal),
+            Mode::Attach => {
+                log::info!("Received {:?}, detaching", signal);
+                self.detaching = true;
+                // Every tracee has to be in a ptrace-stop to be detached
+                for pid in self.tracees.keys() {
+                    if let Err(e) = ptrace::interrupt(*pid) {
+                        log::debug!("Failed to interrupt tracee {}: {}", pid, e);
+                    }
+                }
+            }
+        }
+    }
+    
+    /// Pass a signal sent to minsuki on to the traced command: its whole
+    /// process group if it has its own, otherwise just the command, as
+    /// signalling our shared group would signal us again
+    fn forward_signal(child: Pid, signal: Signal) {
+        let result = match getpgid(Some(child)) {
+            Ok(pgid) if pgid != getpgrp() => signal::killpg(pgid, signal),
+            _ => signal::kill(child, signal),
+        };
+        match result {
+            Ok(()) => log::debug!("Forwarded {:?} to {}", signal, child),
+            Err(e) => log::debug!("Failed to forward {:?} to {}: {}", signal, child, e),
+        }
+    }
 }
 
 pub struct PtraceInterceptor {
-    state_manager: StateManager,
+    emulator: Emulator,
+    trace: Option<TraceLog>,
+    report: Option<Report>,
 }
 
 impl PtraceInterceptor {
     pub fn new(state_file: &str) -> Result<Self> {
         Ok(Self {
-            state_manager: StateManager::new(state_file)?,
+            emulator: Emulator::new(StateManager::new(state_file)?),
+            trace: None,
+            report: None,
         })
     }
     
+    /// Log every intercepted syscall to `trace`. Syscalls that are passed
+    /// through then stop at their exit as well, to record the real result
+    pub fn with_trace(mut self, trace: TraceLog) -> Self {
+        self.trace = Some(trace);
+        self
+    }
+    
+    /// Collect a privilege report over the session, starting from the fake
+    /// state as it is now. Like tracing, this observes passed-through
+    /// syscalls at their exit to catch the ones the kernel still refused
+    pub fn with_report(mut self) -> Self {
+        let state = self.emulator.state_manager().get_state();
+        let report = Report::new(&state.lock().unwrap());
+        self.report = Some(report);
+        self
+    }
+    
+    /// Render the privilege report, if one is being collected
+    pub fn report(&self) -> Option<String> {
+        let report = self.report.as_ref()?;
+        let state = self.emulator.state_manager().get_state();
+        let text = report.render(&state.lock().unwrap());
+        Some(text)
+    }
+    
     pub fn run(&self, command: &[String]) -> Result<()> {
         match unsafe { fork() } {
             Ok(ForkResult::Parent { child }) => {
@@ -215,9 +521,64 @@ impl PtraceInterceptor {
         }
     }
     
+    /// Trace an already running process and all of its threads until it
+    /// exits or minsuki is interrupted, which detaches and leaves it running
+    pub fn attach(&self, pid: Pid) -> Result<()> {
+        log::info!("Attaching to process: {}", pid);
+        let options = Self::trace_options();
+        
+        // Threads can be created while we attach, so keep listing them until
+        // a pass finds nothing new
+        let mut tids: Vec<Pid> = Vec::new();
+        loop {
+            let mut found = false;
+            for tid in Self::threads(pid)? {
+                if tids.contains(&tid) {
+                    continue;
+                }
+                match ptrace::seize(tid, options) {
+                    Ok(()) => {}
+                    // The thread exited in the meantime
+                    Err(Errno::ESRCH) if !tids.is_empty() => continue,
+                    Err(e) => return Err(MinSukiError::Ptrace(format!("seize of {} failed: {}", tid, e))),
+                }
+                // A seized thread keeps running until it is stopped once and
+                // resumed with PTRACE_SYSCALL
+                ptrace::interrupt(tid)
+                    .map_err(|e| MinSukiError::Ptrace(format!("interrupt of {} failed: {}", tid, e)))?;
+                tids.push(tid);
+                found = true;
+            }
+            if !found {
+                break;
+            }
+        }
+        log::debug!("Attached to threads {:?}", tids);
+        
+        self.trace(Session::new(Mode::Attach, pid, &tids))
+    }
+    
+    /// Thread ids of a process, from /proc/<pid>/task
+    fn threads(pid: Pid) -> Result<Vec<Pid>> {
+        let mut tids = Vec::new();
+        for entry in std::fs::read_dir(format!("/proc/{}/task", pid))? {
+            if let Some(tid) = entry?.file_name().to_str().and_then(|name| name.parse().ok()) {
+                tids.push(Pid::from_raw(tid));
+            }
+        }
+        Ok(tids)
+    }
+    
     fn setup_tracee(&self, command: &[String]) -> Result<()> {
-        ptrace::traceme()
-            .map_err(|e| MinSukiError::Ptrace(format!("traceme failed: {}", e)))?;
+        // Only the syscalls we emulate stop the tracee; everything else runs
+        // at full speed. The filter survives exec and is inherited by children.
+        // Forks are ptrace events here, so exits need not stop
+        let syscalls = Syscall::intercepted(|syscall| !matches!(syscall, Syscall::ExitGroup));
+        seccomp::install(&seccomp::filter(&syscalls, libc::SECCOMP_RET_TRACE))?;
+        
+        // Wait for the tracer to seize us before running anything it traces
+        signal::raise(Signal::SIGSTOP)
+            .map_err(|e| MinSukiError::Syscall(format!("raise failed: {}", e)))?;
         
         let program = &command[0];
         let args: Vec<&str> = command.iter().map(|s| s.as_str()).collect();
@@ -233,159 +594,292 @@ impl PtraceInterceptor {
     fn trace_child(&self, child: Pid) -> Result<()> {
         log::info!("Tracing child process: {}", child);
         
-        waitpid(child, None)
+        waitpid(child, Some(WaitPidFlag::WSTOPPED))
             .map_err(|e| MinSukiError::Ptrace(format!("Initial wait failed: {}", e)))?;
         
-        ptrace::setoptions(
-            child,
-            ptrace::Options::PTRACE_O_TRACESYSGOOD | ptrace::Options::PTRACE_O_EXITKILL,
-        ).map_err(|e| MinSukiError::Ptrace(format!("setoptions failed: {}", e)))?;
+        // Seizing rather than PTRACE_TRACEME lets group-stops be told apart
+        // from signal-delivery stops, so job control keeps working. The
+        // command must not outlive us, as it would lose its fake root
+        ptrace::seize(child, Self::trace_options() | ptrace::Options::PTRACE_O_EXITKILL)
+            .map_err(|e| MinSukiError::Ptrace(format!("seize failed: {}", e)))?;
+        signal::kill(child, Signal::SIGCONT)
+            .map_err(|e| MinSukiError::Syscall(format!("cannot continue child: {}", e)))?;
         
-        let mut in_syscall = false;
+        self.trace(Session::new(Mode::Run, child, &[child]))
+    }
+    
+    fn trace(&self, mut session: Session) -> Result<()> {
+        Self::catch_signals()?;
         
-        loop {
-            ptrace::syscall(child, None)
-                .map_err(|e| MinSukiError::Ptrace(format!("syscall continue failed: {}", e)))?;
+        while !session.tracees.is_empty() {
+            let received = RECEIVED_SIGNAL.swap(0, Ordering::Relaxed);
+            if received != 0 {
+                session.handle_signal(received, FROM_TERMINAL.load(Ordering::Relaxed));
+            }
+            
+            let status = match waitpid(Pid::from_raw(-1), Some(WaitPidFlag::__WALL)) {
+                Ok(status) => status,
+                Err(Errno::EINTR) => continue,
+                Err(Errno::ECHILD) => break,
+                Err(e) => return Err(MinSukiError::Ptrace(format!("waitpid failed: {}", e))),
+            };
             
-            match waitpid(child, None) {
-                Ok(WaitStatus::Exited(_, code)) => {
-                    log::info!("Child exited with code: {}", code);
-                    return Ok(());
+            match status {
+                WaitStatus::Exited(pid, code) => {
+                    session.tracees.remove(&pid);
+                    self.emulator.exit(pid);
+                    if pid == session.leader {
+                        log::info!("Child exited with code: {}", code);
+                    } else {
+                        log::debug!("Tracee {} exited with code: {}", pid, code);
+                    }
                 }
-                Ok(WaitStatus::Signaled(_, signal, _)) => {
-                    log::info!("Child killed by signal: {:?}", signal);
-                    return Ok(());
+                WaitStatus::Signaled(pid, signal, _) => {
+                    session.tracees.remove(&pid);
+                    self.emulator.exit(pid);
+                    if pid == session.leader {
+                        log::info!("Child killed by signal: {:?}", signal);
+                    } else {
+                        log::debug!("Tracee {} killed by signal: {:?}", pid, signal);
+                    }
                 }
-                Ok(WaitStatus::PtraceSyscall(_)) => {
-                    if !in_syscall {
-                        if let Err(e) = self.handle_syscall_enter(child) {
-                            log::error!("Error handling syscall enter: {}", e);
+                WaitStatus::PtraceEvent(pid, _, libc::PTRACE_EVENT_SECCOMP) => {
+                    // The filter only traces syscalls we intercept, so this
+                    // stands in for their syscall-enter stop
+                    let tracee = session.tracees.entry(pid).or_default();
+                    match self.handle_syscall_enter(pid) {
+                        Ok(pending) => tracee.pending = pending,
+                        Err(e) => log::error!("Error handling syscall enter: {}", e),
+                    }
+                    session.resume(pid, None);
+                }
+                WaitStatus::PtraceSyscall(pid) => {
+                    let tracee = session.tracees.entry(pid).or_default();
+                    // Spawned tracees only get here at the exit of a syscall
+                    // with a pending exit; attached ones stop at both ends
+                    let entering = session.mode == Mode::Attach && !tracee.in_syscall;
+                    if entering {
+                        match self.handle_syscall_enter(pid) {
+                            Ok(pending) => tracee.pending = pending,
+                            Err(e) => log::error!("Error handling syscall enter: {}", e),
+                        }
+                    } else if let Some(pending) = tracee.pending.take() {
+                        if let Err(e) = self.handle_syscall_exit(pid, pending) {
+                            log::error!("Error handling syscall exit: {}", e);
                         }
                     }
-                    in_syscall = !in_syscall;
+                    tracee.in_syscall = entering;
+                    session.resume(pid, None);
                 }
-                Ok(WaitStatus::Stopped(_, Signal::SIGTRAP)) => {}
-                Ok(status) => {
-                    log::debug!("Unexpected wait status: {:?}", status);
+                WaitStatus::PtraceEvent(pid, Signal::SIGTRAP, libc::PTRACE_EVENT_STOP) => {
+                    // The initial stop of a new tracee, which can be reported
+                    // before its parent's fork event, the end of a group-stop
+                    // after SIGCONT, or a PTRACE_INTERRUPT
+                    session.resume(pid, None);
+                }
+                WaitStatus::PtraceEvent(pid, signal, libc::PTRACE_EVENT_STOP) => {
+                    if session.detaching {
+                        session.resume(pid, None);
+                        continue;
+                    }
+                    // Group-stop: stay stopped like an untraced process would,
+                    // but keep reporting so SIGCONT can be seen
+                    log::debug!("Tracee {} group-stopped by {:?}", pid, signal);
+                    if let Err(e) = Self::listen(pid) {
+                        log::debug!("Failed to listen on tracee {}: {}", pid, e);
+                    }
+                }
+                WaitStatus::PtraceEvent(pid, _, event) => {
+                    self.handle_event(&mut session.tracees, pid, event);
+                    session.resume(pid, None);
                 }
-                Err(e) => {
-                    return Err(MinSukiError::Ptrace(format!("waitpid failed: {}", e)));
+                WaitStatus::Stopped(pid, signal) => {
+                    // Signal-delivery stop; pass the signal on to the tracee.
+                    // Stopping signals then turn into a group-stop above
+                    session.resume(pid, Some(signal));
+                }
+                status => {
+                    log::debug!("Unexpected wait status: {:?}", status);
                 }
             }
         }
-    }
-    
-    fn handle_syscall_enter(&self, pid: Pid) -> Result<()> {
-        let regs = regs::getregs(pid)
-            .map_err(|e| MinSukiError::Ptrace(format!("getregs failed: {}", e)))?;
-        
-        // Get syscall number - architecture specific
-        #[cfg(target_arch = "aarch64")]
-        let syscall_num = regs.regs[8] as i64;
-        
-        #[cfg(target_arch = "arm")]
-        let syscall_num = regs.uregs[7] as i64;
         
-        #[cfg(target_arch = "x86_64")]
-        let syscall_num = regs.orig_rax as i64;
-        
-        #[cfg(target_arch = "x86")]
-        let syscall_num = regs.orig_eax as i64;
-        
-        match syscall_num {
-            syscall::CHOWN | syscall::LCHOWN => {
-                log::debug!("Intercepted chown/lchown syscall");
-                #[cfg(any(target_arch = "aarch64", target_arch = "arm"))]
-                self.handle_chown(pid, regs.regs[0], regs.regs[1] as u32, regs.regs[2] as u32)?;
-                #[cfg(target_arch = "x86_64")]
-                self.handle_chown(pid, regs.rdi, regs.rsi as u32, regs.rdx as u32)?;
-                #[cfg(target_arch = "x86")]
-                self.handle_chown(pid, regs.ebx as u64, regs.ecx as u32, regs.edx as u32)?;
-            }
-            _ => {}
+        if session.detaching {
+            log::info!("Detached from all traced processes");
+        } else {
+            log::info!("All traced processes have exited");
         }
-        
         Ok(())
     }
     
-    fn handle_chown(&self, pid: Pid, path_ptr: u64, uid: u32, gid: u32) -> Result<()> {
-        let path = self.read_string(pid, path_ptr)?;
-        self.state_manager.chown(PathBuf::from(path), uid, gid)?;
-        self.set_syscall_return(pid, 0)?;
-        Ok(())
+    fn trace_options() -> ptrace::Options {
+        ptrace::Options::PTRACE_O_TRACESYSGOOD
+            | ptrace::Options::PTRACE_O_TRACEFORK
+            | ptrace::Options::PTRACE_O_TRACEVFORK
+            | ptrace::Options::PTRACE_O_TRACECLONE
+            | ptrace::Options::PTRACE_O_TRACEEXEC
+            | ptrace::Options::PTRACE_O_TRACESECCOMP
     }
     
-    fn handle_chmod(&self, pid: Pid, path_ptr: u64, mode: u32) -> Result<()> {
-        let path = self.read_string(pid, path_ptr)?;
-        self.state_manager.chmod(PathBuf::from(path), mode)?;
-        self.set_syscall_return(pid, 0)?;
-        Ok(())
-    }
-    
-    fn handle_setuid(&self, uid: u32) -> Result<()> {
-        self.state_manager.setuid(uid)
-    }
-    
-    fn handle_setgid(&self, gid: u32) -> Result<()> {
-        self.state_manager.setgid(gid)
+    fn listen(pid: Pid) -> nix::Result<()> {
+        let ret = unsafe {
+            libc::ptrace(libc::PTRACE_LISTEN, pid.as_raw(), std::ptr::null_mut::<libc::c_void>(), 0)
+        };
+        Errno::result(ret).map(drop)
     }
     
-    fn handle_getuid(&self, pid: Pid) -> Result<()> {
-        let state = self.state_manager.get_state();
-        let state = state.lock().unwrap();
-        let uid = state.current_uid;
-        self.set_syscall_return(pid, uid as i64)?;
+    /// Catch the signals we forward instead of dying on them, which would
+    /// take spawned tracees down with us through PTRACE_O_EXITKILL. No
+    /// SA_RESTART, so a signal interrupts the waitpid in the tracing loop
+    fn catch_signals() -> Result<()> {
+        let action = SigAction::new(SigHandler::SigAction(on_signal), SaFlags::SA_SIGINFO, SigSet::empty());
+        for signal in FORWARDED_SIGNALS {
+            unsafe { signal::sigaction(signal, &action) }
+                .map_err(|e| MinSukiError::Syscall(format!("sigaction failed: {}", e)))?;
+        }
         Ok(())
     }
     
-    fn handle_getgid(&self, pid: Pid) -> Result<()> {
-        let state = self.state_manager.get_state();
-        let state = state.lock().unwrap();
-        let gid = state.current_gid;
-        self.set_syscall_return(pid, gid as i64)?;
-        Ok(())
+    fn handle_event(&self, tracees: &mut HashMap<Pid, Tracee>, pid: Pid, event: i32) {
+        let message = match ptrace::getevent(pid) {
+            Ok(message) => message,
+            Err(e) => {
+                log::debug!("getevent failed for {}: {}", pid, e);
+                return;
+            }
+        };
+        
+        match event {
+            libc::PTRACE_EVENT_FORK | libc::PTRACE_EVENT_VFORK | libc::PTRACE_EVENT_CLONE => {
+                let new_pid = Pid::from_raw(message as i32);
+                log::debug!("Tracee {} spawned {}", pid, new_pid);
+                // Options are inherited by auto-attached children, so the
+                // only thing left is to wait for its initial stop
+                tracees.entry(new_pid).or_default();
+                self.emulator.fork(pid, new_pid);
+            }
+            libc::PTRACE_EVENT_EXEC => {
+                // A non-leader thread calling execve takes over the leader's
+                // pid, and the execve it entered is the one still in flight
+                let former = Pid::from_raw(message as i32);
+                if former != pid {
+                    log::debug!("Thread {} became {} on exec", former, pid);
+                    let tracee = tracees.remove(&former).unwrap_or_default();
+                    tracees.insert(pid, tracee);
+                }
+                self.emulator.exec(former, pid);
+            }
+            _ => {}
+        }
     }
     
-    fn read_string(&self, pid: Pid, addr: u64) -> Result<String> {
-        let mut result = Vec::new();
-        let mut offset = 0;
+    /// Handle a syscall-enter stop, returning the intercepted syscall if its
+    /// exit stop needs attention as well
+    fn handle_syscall_enter(&self, pid: Pid) -> Result<Option<Pending>> {
+        let regs = regs::getregs(pid)
+            .map_err(|e| MinSukiError::Ptrace(format!("getregs failed: {}", e)))?;
         
-        loop {
-            let word = ptrace::read(pid, (addr + offset) as *mut _)
-                .map_err(|e| MinSukiError::Ptrace(format!("read failed: {}", e)))?;
-            
-            let bytes = word.to_ne_bytes();
-            for &byte in &bytes {
-                if byte == 0 {
-                    return String::from_utf8(result)
-                        .map_err(|_| MinSukiError::Ptrace("Invalid UTF-8".to_string()));
+        let sysno = match Sysno::from_number(regs::syscall_number(&regs)) {
+            Some(sysno) => sysno,
+            None => return Ok(None),
+        };
+        let syscall = match Syscall::decode(sysno, &regs::syscall_args(&regs)) {
+            Some(syscall) => syscall,
+            None => return Ok(None),
+        };
+        
+        // The tracee stays stopped, so what was decided still holds
+        let decision = self.emulator.decide(pid, syscall)?;
+        let action = self.emulator.apply(pid, decision)?;
+        log::debug!("Intercepted {} from {}: {:?} -> {:?}", sysno.name(), pid, syscall, action);
+        
+        let pending = Pending { sysno, syscall, action };
+        match action {
+            Action::PassThrough if self.observes_exits() => Ok(Some(pending)),
+            Action::PassThrough => Ok(None),
+            Action::Inspect => Ok(Some(pending)),
+            Action::Emulate(_) => {
+                regs::skip_syscall(pid)
+                    .map_err(|e| MinSukiError::Ptrace(format!("cannot cancel syscall: {}", e)))?;
+                Ok(Some(pending))
+            }
+        }
+    }
+    
+    /// Handle the syscall-exit stop of an intercepted syscall
+    fn handle_syscall_exit(&self, pid: Pid, pending: Pending) -> Result<()> {
+        let (real, fake) = match (pending.action, pending.syscall) {
+            (Action::Emulate(result), _) => {
+                self.set_syscall_return(pid, result)?;
+                (None, Some(result))
+            }
+            (Action::Inspect, Syscall::Access { target, mode, flags }) => {
+                // Turn a permission failure into the answer root would get;
+                // real errors such as ENOENT are left alone
+                let real = self.syscall_return(pid)?;
+                let mut fake = real;
+                if real == -(libc::EACCES as i64) && self.emulator.access_granted(pid, target, mode, flags)? {
+                    self.set_syscall_return(pid, 0)?;
+                    fake = 0;
                 }
-                result.push(byte);
+                (Some(real), Some(fake))
             }
-            
-            offset += 8;
-            if offset > 4096 {
-                return Err(MinSukiError::Ptrace("String too long".to_string()));
+            (Action::Inspect, Syscall::Stat { target, buf, layout }) => {
+                let real = self.syscall_return(pid)?;
+                if real == 0 {
+                    self.emulator.overlay_stat(pid, target, buf, layout)?;
+                }
+                (Some(real), Some(real))
+            }
+            (Action::Inspect, Syscall::Create { target, node }) => {
+                let real = self.syscall_return(pid)?;
+                if real >= 0 {
+                    self.emulator.record_creation(pid, target, node)?;
+                }
+                (Some(real), Some(real))
+            }
+            _ => (Some(self.syscall_return(pid)?), None),
+        };
+        
+        if self.observes_exits() {
+            let event = TraceEvent {
+                pid: pid.as_raw(),
+                syscall: pending.sysno.name().to_string(),
+                args: pending.syscall.trace_args(&self.emulator, pid),
+                disposition: match pending.action {
+                    Action::PassThrough => Disposition::Passthrough,
+                    Action::Inspect => Disposition::Inspected,
+                    Action::Emulate(_) => Disposition::Emulated,
+                },
+                fake_result: fake,
+                real_result: real,
+            };
+            if let Some(trace) = &self.trace {
+                trace.record(&event);
+            }
+            if let Some(report) = &self.report {
+                report.record(&event);
             }
         }
+        Ok(())
+    }
+    
+    /// Whether every intercepted syscall is seen through to its result
+    fn observes_exits(&self) -> bool {
+        self.trace.is_some() || self.report.is_some()
+    }
+    
+    fn syscall_return(&self, pid: Pid) -> Result<i64> {
+        let regs = regs::getregs(pid)
+            .map_err(|e| MinSukiError::Ptrace(format!("getregs failed: {}", e)))?;
+        Ok(regs::syscall_return(&regs))
     }
     
     fn set_syscall_return(&self, pid: Pid, value: i64) -> Result<()> {
         let mut regs = regs::getregs(pid)
             .map_err(|e| MinSukiError::Ptrace(format!("getregs failed: {}", e)))?;
         
-        // Set return value - architecture specific
-        #[cfg(target_arch = "aarch64")]
-        { regs.regs[0] = value as u64; }
-        
-        #[cfg(target_arch = "arm")]
-        { regs.uregs[0] = value as u32; }
-        
-        #[cfg(target_arch = "x86_64")]
-        { regs.rax = value as u64; }
-        
-        #[cfg(target_arch = "x86")]
-        { regs.eax = value as u32; }
+        regs::set_return(&mut regs, value);
         
         regs::setregs(pid, regs)
             .map_err(|e| MinSukiError::Ptrace(format!("setregs failed: {}", e)))?;
@@ -393,3 +887,30 @@ impl PtraceInterceptor {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::MetadataExt;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_shell_runs_as_fake_root() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("file");
+        std::fs::write(&file, "").unwrap();
+        let owner = std::fs::metadata(&file).unwrap().uid();
+        let state_file = dir.path().join("state");
+        
+        let script = format!("id -u > {0}/uid && chown 123:456 {0}/file", dir.path().display());
+        let command = ["sh".to_string(), "-c".to_string(), script];
+        PtraceInterceptor::new(state_file.to_str().unwrap()).unwrap().run(&command).unwrap();
+        
+        assert_eq!(std::fs::read_to_string(dir.path().join("uid")).unwrap(), "0\n");
+        let state = StateManager::new(state_file.to_str().unwrap()).unwrap().get_state();
+        let state = state.lock().unwrap();
+        let meta = state.get_metadata(&file.canonicalize().unwrap()).unwrap();
+        assert_eq!((meta.uid, meta.gid), (123, 456));
+        assert_eq!(std::fs::metadata(&file).unwrap().uid(), owner);
+    }
+}
@@ -0,0 +1,238 @@
+use crate::caps::Capability;
+use crate::trace::{Disposition, TraceEvent};
+use crate::types::{FakeMetadata, FakeState};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Per-syscall counters, in the spirit of `strace -c`
+#[derive(Debug, Default, Clone, Copy)]
+struct Counts {
+    calls: u64,
+    emulated: u64,
+    denied: u64,
+}
+
+/// A syscall that still failed with EPERM or EACCES under emulation
+#[derive(Debug, Clone)]
+struct Denial {
+    pid: i32,
+    syscall: String,
+    path: Option<PathBuf>,
+    errno: i32,
+}
+
+#[derive(Debug, Default)]
+struct Summary {
+    counts: BTreeMap<String, Counts>,
+    identity: Vec<(i32, String, u32)>,
+    capabilities: BTreeMap<Capability, u64>,
+    denials: Vec<Denial>,
+}
+
+/// Summary of the privileged operations of a session, built from the
+/// intercepted syscalls and the fake state before and after it
+pub struct Report {
+    initial: HashMap<PathBuf, FakeMetadata>,
+    summary: Mutex<Summary>,
+}
+
+impl Report {
+    /// Start a report, remembering the fake metadata as it was
+    pub fn new(state: &FakeState) -> Self {
+        Self {
+            initial: state.files.clone(),
+            summary: Mutex::new(Summary::default()),
+        }
+    }
+    
+    pub fn record(&self, event: &TraceEvent) {
+        let mut summary = self.summary.lock().unwrap();
+        let result = event.fake_result.or(event.real_result);
+        let denied = matches!(result, Some(r) if r == -(libc::EPERM as i64) || r == -(libc::EACCES as i64));
+        let emulated = event.disposition == Disposition::Emulated;
+        
+        let counts = summary.counts.entry(event.syscall.clone()).or_default();
+        counts.calls += 1;
+        counts.emulated += emulated as u64;
+        counts.denied += denied as u64;
+        
+        if denied {
+            summary.denials.push(Denial {
+                pid: event.pid,
+                syscall: event.syscall.clone(),
+                path: event.args.path.clone(),
+                errno: -result.unwrap_or(0) as i32,
+            });
+        }
+        
+        // A refused call switched nothing and exercised no capability
+        if !emulated || denied || matches!(result, Some(r) if r < 0) {
+            return;
+        }
+        if let Some(capability) = capability_for(event) {
+            *summary.capabilities.entry(capability).or_default() += 1;
+        }
+        if event.syscall.starts_with("set") {
+            if let Some(id) = event.args.uid.or(event.args.gid) {
+                summary.identity.push((event.pid, event.syscall.clone(), id));
+            }
+        }
+    }
+    
+    /// Render the report against the fake state at the end of the session
+    pub fn render(&self, state: &FakeState) -> String {
+        let summary = self.summary.lock().unwrap();
+        let mut out = String::new();
+        
+        let _ = writeln!(out, "📋 MinSuki Privilege Report");
+        let _ = writeln!(out, "===========================");
+        let _ = writeln!(out);
+        
+        let mut changed: Vec<_> = state
+            .files
+            .iter()
+            .filter(|(path, meta)| self.initial.get(*path).is_none_or(|old| !same(old, meta)))
+            .collect();
+        changed.sort_by(|a, b| a.0.cmp(b.0));
+        let _ = writeln!(out, "Ownership and mode changes ({}):", changed.len());
+        for (path, meta) in changed {
+            let before = self.initial.get(path).cloned().or_else(|| real_metadata(path));
+            match before {
+                Some(old) => {
+                    let _ = writeln!(
+                        out,
+                        "  {}: {}:{} {:o} -> {}:{} {:o}",
+                        path.display(), old.uid, old.gid, old.mode, meta.uid, meta.gid, meta.mode
+                    );
+                }
+                None => {
+                    let _ = writeln!(out, "  {}: -> {}:{} {:o}", path.display(), meta.uid, meta.gid, meta.mode);
+                }
+            }
+        }
+        let _ = writeln!(out);
+        
+        let _ = writeln!(out, "Identity switches ({}):", summary.identity.len());
+        for (pid, syscall, id) in &summary.identity {
+            let _ = writeln!(out, "  [{}] {}({})", pid, syscall, id);
+        }
+        let _ = writeln!(out);
+        
+        let _ = writeln!(out, "Capabilities exercised:");
+        for (capability, count) in &summary.capabilities {
+            let _ = writeln!(out, "  {:<14} {}", capability.name().to_ascii_uppercase(), count);
+        }
+        let _ = writeln!(out);
+        
+        let _ = writeln!(out, "Denied despite emulation ({}):", summary.denials.len());
+        for denial in &summary.denials {
+            let path = denial.path.as_ref().map(|p| format!(" {}", p.display())).unwrap_or_default();
+            let _ = writeln!(
+                out,
+                "  [{}] {}{}: {}",
+                denial.pid, denial.syscall, path, std::io::Error::from_raw_os_error(denial.errno)
+            );
+        }
+        let _ = writeln!(out);
+        
+        let _ = writeln!(out, "{:>8} {:>8} {:>8}  syscall", "calls", "emulated", "denied");
+        let _ = writeln!(out, "{:>8} {:>8} {:>8}  -------", "-----", "--------", "------");
+        let mut total = Counts::default();
+        for (syscall, counts) in &summary.counts {
+            let _ = writeln!(out, "{:>8} {:>8} {:>8}  {}", counts.calls, counts.emulated, counts.denied, syscall);
+            total.calls += counts.calls;
+            total.emulated += counts.emulated;
+            total.denied += counts.denied;
+        }
+        let _ = writeln!(out, "{:>8} {:>8} {:>8}  total", total.calls, total.emulated, total.denied);
+        
+        out
+    }
+}
+
+/// The capability a real process would need for a call minsuki emulated
+fn capability_for(event: &TraceEvent) -> Option<Capability> {
+    let syscall = event.syscall.as_str();
+    if syscall.contains("chown") {
+        Some(Capability::Chown)
+    } else if syscall.contains("chmod") {
+        Some(Capability::Fowner)
+    } else if syscall.starts_with("set") && syscall.contains("uid") {
+        Some(Capability::Setuid)
+    } else if syscall.starts_with("set") && (syscall.contains("gid") || syscall.contains("groups")) {
+        Some(Capability::Setgid)
+    } else if syscall == "capset" {
+        Some(Capability::Setpcap)
+    } else if syscall == "prctl" {
+        // Only the options that change capabilities, not the queries
+        let changes = match event.args.option {
+            Some(libc::PR_SET_KEEPCAPS | libc::PR_CAPBSET_DROP) => true,
+            Some(libc::PR_CAP_AMBIENT) => event.args.arg == Some(libc::PR_CAP_AMBIENT_RAISE as u64),
+            _ => false,
+        };
+        changes.then_some(Capability::Setpcap)
+    } else {
+        None
+    }
+}
+
+fn same(a: &FakeMetadata, b: &FakeMetadata) -> bool {
+    a.uid == b.uid && a.gid == b.gid && a.mode == b.mode
+}
+
+/// What the file looks like on disk, which emulation never changes
+fn real_metadata(path: &PathBuf) -> Option<FakeMetadata> {
+    std::fs::symlink_metadata(path).ok().map(|meta| FakeMetadata::from(&meta))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trace::TraceArgs;
+    
+    fn event(syscall: &str, disposition: Disposition, fake: Option<i64>, real: Option<i64>) -> TraceEvent {
+        TraceEvent {
+            pid: 1,
+            syscall: syscall.to_string(),
+            args: TraceArgs { uid: Some(0), ..Default::default() },
+            disposition,
+            fake_result: fake,
+            real_result: real,
+        }
+    }
+    
+    #[test]
+    fn test_report_summarizes_privilege() {
+        let mut state = FakeState::default();
+        let report = Report::new(&state);
+        state.chown("/nonexistent/minsuki".into(), 0, 0);
+        
+        report.record(&event("setuid", Disposition::Emulated, Some(0), None));
+        report.record(&event("fchownat", Disposition::Emulated, Some(0), None));
+        report.record(&event("fchownat", Disposition::Passthrough, None, Some(-(libc::EPERM as i64))));
+        
+        let text = report.render(&state);
+        assert!(text.contains("Ownership and mode changes (1):"));
+        assert!(text.contains("[1] setuid(0)"));
+        assert!(text.contains("CAP_CHOWN      1"));
+        assert!(text.contains("Denied despite emulation (1):"));
+        assert!(text.contains("       2        1        1  fchownat"));
+    }
+    
+    #[test]
+    fn test_report_skips_refused_switch() {
+        let state = FakeState::default();
+        let report = Report::new(&state);
+        
+        report.record(&event("setuid", Disposition::Emulated, Some(-(libc::EPERM as i64)), None));
+        report.record(&event("capset", Disposition::Emulated, Some(0), None));
+        
+        let text = report.render(&state);
+        assert!(text.contains("Identity switches (0):"));
+        assert!(!text.contains("CAP_SETUID"));
+        assert!(text.contains("CAP_SETPCAP    1"));
+        assert!(text.contains("Denied despite emulation (1):"));
+    }
+}
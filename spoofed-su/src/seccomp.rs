@@ -0,0 +1,427 @@
+use crate::emulate::{Action, Emulator, Syscall};
+use crate::state::StateManager;
+use crate::syscalls::Sysno;
+use crate::trace::{Disposition, TraceEvent, TraceLog};
+use crate::types::{MinSukiError, Result};
+use libc::{sock_filter, BPF_JUMP, BPF_STMT};
+use nix::errno::Errno;
+use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
+use nix::unistd::{fork, ForkResult, Pid};
+use std::os::unix::io::RawFd;
+
+/// AUDIT_ARCH_* value the kernel reports in `seccomp_data.arch` for native
+/// syscalls of the architecture minsuki was built for
+#[cfg(target_arch = "x86_64")]
+pub const AUDIT_ARCH: u32 = 0xc000_003e;
+#[cfg(target_arch = "x86")]
+pub const AUDIT_ARCH: u32 = 0x4000_0003;
+#[cfg(target_arch = "aarch64")]
+pub const AUDIT_ARCH: u32 = 0xc000_00b7;
+#[cfg(target_arch = "arm")]
+pub const AUDIT_ARCH: u32 = 0x4000_0028;
+#[cfg(target_arch = "riscv64")]
+pub const AUDIT_ARCH: u32 = 0xc000_00f3;
+
+/// Offsets of the fields of `struct seccomp_data` the filters look at
+const DATA_NR: u32 = 0;
+const DATA_ARCH: u32 = 4;
+const DATA_ARGS: u32 = 16;
+
+/// The argument holding the open flags of a syscall that only needs to
+/// stop when it may create a file. openat2 keeps its flags in a struct
+/// open_how, out of reach of the filter, so it always stops
+fn open_flags_arg(nr: i64) -> Option<u32> {
+    [(Sysno::Open, 1), (Sysno::Openat, 2)]
+        .into_iter()
+        .find(|(sysno, _)| sysno.number() == Some(nr))
+        .map(|(_, arg)| arg)
+}
+
+/// Build a filter that hands the listed native syscalls to `action` and
+/// lets everything else, including other ABIs, run untouched. open and
+/// openat are only handed over with O_CREAT or O_TMPFILE set
+pub fn filter(syscalls: &[i64], action: u32) -> Vec<sock_filter> {
+    let ld = (libc::BPF_LD | libc::BPF_W | libc::BPF_ABS) as u16;
+    let jeq = (libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K) as u16;
+    let jset = (libc::BPF_JMP | libc::BPF_JSET | libc::BPF_K) as u16;
+    let ret = (libc::BPF_RET | libc::BPF_K) as u16;
+    
+    let mut program = unsafe {
+        vec![
+            BPF_STMT(ld, DATA_ARCH),
+            BPF_JUMP(jeq, AUDIT_ARCH, 1, 0),
+            BPF_STMT(ret, libc::SECCOMP_RET_ALLOW),
+            BPF_STMT(ld, DATA_NR),
+        ]
+    };
+    
+    // Each match jumps over the remaining comparisons and the final allow,
+    // or further on to the flag check of an open
+    let mut flag_checks = Vec::new();
+    for (i, &nr) in syscalls.iter().enumerate() {
+        let mut skip = syscalls.len() - i;
+        if let Some(arg) = open_flags_arg(nr) {
+            skip += 1 + 4 * flag_checks.len();
+            flag_checks.push(arg);
+        }
+        program.push(unsafe { BPF_JUMP(jeq, nr as u32, skip as u8, 0) });
+    }
+    
+    unsafe {
+        program.push(BPF_STMT(ret, libc::SECCOMP_RET_ALLOW));
+        program.push(BPF_STMT(ret, action));
+    }
+    
+    // The flags are an int, the low half of the argument
+    let creating = (libc::O_CREAT | (libc::O_TMPFILE & !libc::O_DIRECTORY)) as u32;
+    let low = if cfg!(target_endian = "big") { 4 } else { 0 };
+    for arg in flag_checks {
+        unsafe {
+            program.push(BPF_STMT(ld, DATA_ARGS + 8 * arg + low));
+            program.push(BPF_JUMP(jset, creating, 0, 1));
+            program.push(BPF_STMT(ret, action));
+            program.push(BPF_STMT(ret, libc::SECCOMP_RET_ALLOW));
+        }
+    }
+    program
+}
+
+/// Install a filter on the calling thread, setting no_new_privs first as
+/// an unprivileged process must
+pub fn install(program: &[sock_filter]) -> Result<()> {
+    let fprog = fprog(program);
+    set_no_new_privs()?;
+    
+    if unsafe { libc::prctl(libc::PR_SET_SECCOMP, libc::SECCOMP_MODE_FILTER, &fprog as *const libc::sock_fprog) } != 0 {
+        return Err(MinSukiError::Syscall(format!(
+            "installing seccomp filter failed: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+    
+    Ok(())
+}
+
+/// Install a filter whose SECCOMP_RET_USER_NOTIF verdicts are delivered to
+/// the returned listener fd
+pub fn install_listener(program: &[sock_filter]) -> Result<RawFd> {
+    let fprog = fprog(program);
+    set_no_new_privs()?;
+    
+    let fd = unsafe {
+        libc::syscall(
+            libc::SYS_seccomp,
+            libc::SECCOMP_SET_MODE_FILTER,
+            libc::SECCOMP_FILTER_FLAG_NEW_LISTENER,
+            &fprog as *const libc::sock_fprog,
+        )
+    };
+    if fd < 0 {
+        return Err(MinSukiError::Syscall(format!(
+            "installing seccomp listener failed: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+    
+    Ok(fd as RawFd)
+}
+
+fn fprog(program: &[sock_filter]) -> libc::sock_fprog {
+    libc::sock_fprog {
+        len: program.len() as u16,
+        filter: program.as_ptr() as *mut sock_filter,
+    }
+}
+
+fn set_no_new_privs() -> Result<()> {
+    if unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) } != 0 {
+        return Err(MinSukiError::Syscall(format!(
+            "PR_SET_NO_NEW_PRIVS failed: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+    Ok(())
+}
+
+/// ioctls on a seccomp listener fd, from <linux/seccomp.h>
+const SECCOMP_IOCTL_NOTIF_RECV: u64 = 0xc050_2100;
+const SECCOMP_IOCTL_NOTIF_SEND: u64 = 0xc018_2101;
+const SECCOMP_IOCTL_NOTIF_ID_VALID: u64 = 0x4008_2102;
+
+/// How long to wait for a notification before checking on the child
+const POLL_TIMEOUT_MS: i32 = 100;
+
+/// Supervisor backend built on seccomp user notifications.
+///
+/// The command installs a filter that suspends it on every syscall we can
+/// emulate and hands the listener fd back to us. Each notification is then
+/// either answered from the fake state or sent on to the kernel with
+/// SECCOMP_USER_NOTIF_FLAG_CONTINUE. Unlike ptrace, nothing else ever stops,
+/// but results cannot be rewritten after the fact, so stat and access are
+/// not faked in this mode.
+pub struct SeccompInterceptor {
+    emulator: Emulator,
+    trace: Option<TraceLog>,
+}
+
+impl SeccompInterceptor {
+    pub fn new(state_file: &str) -> Result<Self> {
+        Ok(Self {
+            emulator: Emulator::new(StateManager::new(state_file)?),
+            trace: None,
+        })
+    }
+    
+    /// Log every notification to `trace`. Real results of syscalls sent on
+    /// to the kernel are never seen in this mode
+    pub fn with_trace(mut self, trace: TraceLog) -> Self {
+        self.trace = Some(trace);
+        self
+    }
+    
+    pub fn run(&self, command: &[String]) -> Result<()> {
+        let mut sockets = [0 as RawFd; 2];
+        if unsafe { libc::socketpair(libc::AF_UNIX, libc::SOCK_STREAM | libc::SOCK_CLOEXEC, 0, sockets.as_mut_ptr()) } != 0 {
+            return Err(MinSukiError::Syscall(format!(
+                "socketpair failed: {}",
+                std::io::Error::last_os_error()
+            )));
+        }
+        
+        match unsafe { fork() } {
+            Ok(ForkResult::Parent { child }) => {
+                unsafe { libc::close(sockets[1]) };
+                let listener = recv_fd(sockets[0]);
+                unsafe { libc::close(sockets[0]) };
+                
+                match listener {
+                    Ok(listener) => {
+                        let result = self.supervise(child, listener);
+                        unsafe { libc::close(listener) };
+                        result
+                    }
+                    Err(e) => {
+                        let _ = waitpid(child, None);
+                        Err(e)
+                    }
+                }
+            }
+            Ok(ForkResult::Child) => {
+                unsafe { libc::close(sockets[0]) };
+                self.setup_child(sockets[1], command)
+            }
+            Err(e) => Err(MinSukiError::Syscall(format!("Fork failed: {}", e))),
+        }
+    }
+    
+    fn setup_child(&self, socket: RawFd, command: &[String]) -> Result<()> {
+        let syscalls = Syscall::intercepted(|syscall| !syscall.needs_exit());
+        let listener = install_listener(&filter(&syscalls, libc::SECCOMP_RET_USER_NOTIF))?;
+        
+        // Nothing between here and exec may make an intercepted syscall, as
+        // it would wait for a supervisor that does not have the fd yet
+        send_fd(socket, listener)?;
+        unsafe {
+            libc::close(listener);
+            libc::close(socket);
+        }
+        
+        let program = &command[0];
+        let args: Vec<&str> = command.iter().map(|s| s.as_str()).collect();
+        
+        nix::unistd::execvp(
+            &std::ffi::CString::new(program.as_str()).unwrap(),
+            &args.iter().map(|s| std::ffi::CString::new(*s).unwrap()).collect::<Vec<_>>()
+        ).map_err(|e| MinSukiError::Syscall(format!("execvp failed: {}", e)))?;
+        
+        unreachable!()
+    }
+    
+    /// Answer notifications until every process sharing the filter is gone
+    fn supervise(&self, child: Pid, listener: RawFd) -> Result<()> {
+        log::info!("Supervising child process: {}", child);
+        let mut child_running = true;
+        
+        loop {
+            let mut pollfd = libc::pollfd { fd: listener, events: libc::POLLIN, revents: 0 };
+            let ready = unsafe { libc::poll(&mut pollfd, 1, POLL_TIMEOUT_MS) };
+            if ready < 0 && Errno::last() != Errno::EINTR {
+                return Err(MinSukiError::Syscall(format!("poll failed: {}", Errno::last())));
+            }
+            
+            if pollfd.revents & libc::POLLIN != 0 {
+                if let Err(e) = self.handle_notification(listener) {
+                    log::error!("Error handling notification: {}", e);
+                }
+            } else if pollfd.revents & libc::POLLHUP != 0 {
+                break;
+            } else if ready == 0 {
+                // Exits are not reported to us, so this is the moment to
+                // drop identities before their pids get reused
+                self.emulator.forget_exited();
+            }
+            
+            // The filter outlives the child until it is reaped, so reap it
+            // here or the listener never hangs up
+            if child_running {
+                match waitpid(child, Some(WaitPidFlag::WNOHANG)) {
+                    Ok(WaitStatus::Exited(_, code)) => {
+                        log::info!("Child exited with code: {}", code);
+                        child_running = false;
+                    }
+                    Ok(WaitStatus::Signaled(_, signal, _)) => {
+                        log::info!("Child killed by signal: {:?}", signal);
+                        child_running = false;
+                    }
+                    Err(Errno::ECHILD) => child_running = false,
+                    _ => {}
+                }
+            }
+        }
+        
+        log::info!("All supervised processes have exited");
+        Ok(())
+    }
+    
+    fn handle_notification(&self, listener: RawFd) -> Result<()> {
+        let mut notif: libc::seccomp_notif = unsafe { std::mem::zeroed() };
+        if unsafe { libc::ioctl(listener, SECCOMP_IOCTL_NOTIF_RECV as _, &mut notif) } != 0 {
+            // ENOENT: the caller died before we got to it
+            return match Errno::last() {
+                Errno::ENOENT | Errno::EINTR => Ok(()),
+                e => Err(MinSukiError::Syscall(format!("NOTIF_RECV failed: {}", e))),
+            };
+        }
+        
+        let mut resp = libc::seccomp_notif_resp {
+            id: notif.id,
+            val: 0,
+            error: 0,
+            flags: libc::SECCOMP_USER_NOTIF_FLAG_CONTINUE as u32,
+        };
+        match self.decide(listener, &notif) {
+            Ok(Action::Emulate(result)) if result < 0 => {
+                resp.error = result as i32;
+                resp.flags = 0;
+            }
+            Ok(Action::Emulate(result)) => {
+                resp.val = result;
+                resp.flags = 0;
+            }
+            Ok(_) => {}
+            Err(e) => log::error!("Error handling {}: {}", notif.data.nr, e),
+        }
+        
+        if unsafe { libc::ioctl(listener, SECCOMP_IOCTL_NOTIF_SEND as _, &resp) } != 0 {
+            return match Errno::last() {
+                Errno::ENOENT => Ok(()),
+                e => Err(MinSukiError::Syscall(format!("NOTIF_SEND failed: {}", e))),
+            };
+        }
+        Ok(())
+    }
+    
+    fn decide(&self, listener: RawFd, notif: &libc::seccomp_notif) -> Result<Action> {
+        let pid = Pid::from_raw(notif.pid as i32);
+        let sysno = match Sysno::from_number(notif.data.nr as i64) {
+            Some(sysno) => sysno,
+            None => return Ok(Action::PassThrough),
+        };
+        let syscall = match Syscall::decode(sysno, &notif.data.args) {
+            Some(syscall) => syscall,
+            None => return Ok(Action::PassThrough),
+        };
+        
+        let decision = self.emulator.decide(pid, syscall)?;
+        
+        // The pid could have been recycled if the caller died since the
+        // notification was queued, and then what decide read from
+        // /proc/<pid> belonged to someone else; only trust it once the
+        // caller is known to be still waiting. Applying the decision is
+        // deferred until then on purpose, so that no fake metadata,
+        // identity or memory of another process is changed on its behalf
+        if unsafe { libc::ioctl(listener, SECCOMP_IOCTL_NOTIF_ID_VALID as _, &notif.id) } != 0 {
+            return Ok(Action::PassThrough);
+        }
+        let action = self.emulator.apply(pid, decision)?;
+        log::debug!("Intercepted {} from {}: {:?} -> {:?}", sysno.name(), pid, syscall, action);
+        
+        if let Some(trace) = &self.trace {
+            let (disposition, fake_result) = match action {
+                Action::Emulate(result) => (Disposition::Emulated, Some(result)),
+                _ => (Disposition::Passthrough, None),
+            };
+            trace.record(&TraceEvent {
+                pid: pid.as_raw(),
+                syscall: sysno.name().to_string(),
+                args: syscall.trace_args(&self.emulator, pid),
+                disposition,
+                fake_result,
+                real_result: None,
+            });
+        }
+        Ok(action)
+    }
+}
+
+/// Pass a file descriptor over a unix socket as SCM_RIGHTS
+fn send_fd(socket: RawFd, fd: RawFd) -> Result<()> {
+    let mut byte = 0u8;
+    let mut iov = libc::iovec { iov_base: &mut byte as *mut u8 as *mut libc::c_void, iov_len: 1 };
+    let space = unsafe { libc::CMSG_SPACE(std::mem::size_of::<RawFd>() as u32) } as usize;
+    let mut control = vec![0u8; space];
+    
+    let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+    msg.msg_controllen = space as _;
+    
+    unsafe {
+        let cmsg = libc::CMSG_FIRSTHDR(&msg);
+        (*cmsg).cmsg_level = libc::SOL_SOCKET;
+        (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+        (*cmsg).cmsg_len = libc::CMSG_LEN(std::mem::size_of::<RawFd>() as u32) as _;
+        std::ptr::write_unaligned(libc::CMSG_DATA(cmsg) as *mut RawFd, fd);
+        
+        if libc::sendmsg(socket, &msg, 0) < 0 {
+            return Err(MinSukiError::Syscall(format!(
+                "sending listener fd failed: {}",
+                std::io::Error::last_os_error()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Receive a file descriptor sent with `send_fd`
+fn recv_fd(socket: RawFd) -> Result<RawFd> {
+    let mut byte = 0u8;
+    let mut iov = libc::iovec { iov_base: &mut byte as *mut u8 as *mut libc::c_void, iov_len: 1 };
+    let space = unsafe { libc::CMSG_SPACE(std::mem::size_of::<RawFd>() as u32) } as usize;
+    let mut control = vec![0u8; space];
+    
+    let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+    msg.msg_controllen = space as _;
+    
+    unsafe {
+        let n = libc::recvmsg(socket, &mut msg, libc::MSG_CMSG_CLOEXEC);
+        if n < 0 {
+            return Err(MinSukiError::Syscall(format!(
+                "receiving listener fd failed: {}",
+                std::io::Error::last_os_error()
+            )));
+        }
+        
+        let cmsg = libc::CMSG_FIRSTHDR(&msg);
+        if n == 0 || cmsg.is_null() || (*cmsg).cmsg_type != libc::SCM_RIGHTS {
+            return Err(MinSukiError::Syscall(
+                "command exited before installing its seccomp filter".to_string(),
+            ));
+        }
+        Ok(std::ptr::read_unaligned(libc::CMSG_DATA(cmsg) as *const RawFd))
+    }
+}
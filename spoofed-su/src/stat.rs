@@ -0,0 +1,179 @@
+use crate::types::FakeMetadata;
+
+/// Width of an id or mode field in a kernel stat buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Width {
+    U16,
+    U32,
+}
+
+/// Location of a field inside a kernel stat buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Field {
+    pub offset: usize,
+    pub width: Width,
+}
+
+impl Field {
+    const fn u16(offset: usize) -> Self {
+        Self { offset, width: Width::U16 }
+    }
+    
+    const fn u32(offset: usize) -> Self {
+        Self { offset, width: Width::U32 }
+    }
+    
+    fn end(&self) -> usize {
+        self.offset + match self.width {
+            Width::U16 => 2,
+            Width::U32 => 4,
+        }
+    }
+    
+    fn get(&self, buf: &[u8]) -> u32 {
+        let bytes = &buf[self.offset..self.end()];
+        match self.width {
+            Width::U16 => u16::from_ne_bytes([bytes[0], bytes[1]]) as u32,
+            Width::U32 => u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        }
+    }
+    
+    fn set(&self, buf: &mut [u8], value: u32) {
+        let end = self.end();
+        match self.width {
+            Width::U16 => buf[self.offset..end].copy_from_slice(&(value as u16).to_ne_bytes()),
+            Width::U32 => buf[self.offset..end].copy_from_slice(&value.to_ne_bytes()),
+        }
+    }
+    
+    /// Store an id, folding ids a 16-bit field cannot hold to the overflow id
+    fn set_id(&self, buf: &mut [u8], id: u32) {
+        const OVERFLOW_ID: u32 = 65534;
+        match self.width {
+            Width::U16 if id > u16::MAX as u32 => self.set(buf, OVERFLOW_ID),
+            _ => self.set(buf, id),
+        }
+    }
+}
+
+/// Where a kernel stat structure keeps the fields minsuki fakes
+#[derive(Debug, PartialEq, Eq)]
+pub struct StatLayout {
+    pub mode: Field,
+    pub uid: Field,
+    pub gid: Field,
+    
+    /// statx only fills in the fields listed in its leading stx_mask
+    pub statx: bool,
+}
+
+impl StatLayout {
+    /// Number of leading bytes that cover every field of interest
+    pub fn prefix_len(&self) -> usize {
+        self.mode.end().max(self.uid.end()).max(self.gid.end())
+    }
+}
+
+/// `struct stat` of x86_64 (stat, lstat, fstat, newfstatat)
+#[cfg(target_arch = "x86_64")]
+pub const STAT: StatLayout = StatLayout {
+    mode: Field::u32(24),
+    uid: Field::u32(28),
+    gid: Field::u32(32),
+    statx: false,
+};
+
+/// asm-generic `struct stat` of arm64 and riscv64 (fstat, newfstatat)
+#[cfg(any(target_arch = "aarch64", target_arch = "riscv64"))]
+pub const STAT: StatLayout = StatLayout {
+    mode: Field::u32(16),
+    uid: Field::u32(24),
+    gid: Field::u32(28),
+    statx: false,
+};
+
+/// The old `struct stat` of arm and x86 with 16-bit ids
+#[cfg(any(target_arch = "arm", target_arch = "x86"))]
+pub const STAT: StatLayout = StatLayout {
+    mode: Field::u16(8),
+    uid: Field::u16(12),
+    gid: Field::u16(14),
+    statx: false,
+};
+
+/// `struct stat64` of arm and x86, the only architectures with the stat64 calls
+pub const STAT64: StatLayout = StatLayout {
+    mode: Field::u32(16),
+    uid: Field::u32(24),
+    gid: Field::u32(28),
+    statx: false,
+};
+
+/// `struct statx`, identical on every architecture
+pub const STATX: StatLayout = StatLayout {
+    mode: Field::u16(28),
+    uid: Field::u32(20),
+    gid: Field::u32(24),
+    statx: true,
+};
+
+const STATX_TYPE: u32 = 0x1;
+const STATX_MODE: u32 = 0x2;
+const STATX_UID: u32 = 0x8;
+const STATX_GID: u32 = 0x10;
+
+/// Overlay fake ownership and permission bits onto the leading
+/// `layout.prefix_len()` bytes of a kernel stat buffer, keeping the file type
+pub fn overlay(layout: &StatLayout, buf: &mut [u8], meta: &FakeMetadata) {
+    let mask = if layout.statx {
+        Field::u32(0).get(buf)
+    } else {
+        STATX_TYPE | STATX_MODE | STATX_UID | STATX_GID
+    };
+    
+    if mask & STATX_UID != 0 {
+        layout.uid.set_id(buf, meta.uid);
+    }
+    if mask & STATX_GID != 0 {
+        layout.gid.set_id(buf, meta.gid);
+    }
+    if mask & STATX_MODE != 0 {
+        let mode = layout.mode.get(buf);
+        layout.mode.set(buf, (mode & libc::S_IFMT) | (meta.mode & 0o7777));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn meta(uid: u32, gid: u32, mode: u32) -> FakeMetadata {
+        FakeMetadata { uid, gid, mode, ..FakeMetadata::default() }
+    }
+    
+    #[test]
+    fn test_overlay_keeps_file_type() {
+        let mut buf = vec![0u8; STAT.prefix_len()];
+        STAT.mode.set(&mut buf, libc::S_IFREG | 0o644);
+        STAT.uid.set(&mut buf, 1000);
+        
+        overlay(&STAT, &mut buf, &meta(0, 0, 0o4755));
+        
+        assert_eq!(STAT.mode.get(&buf), libc::S_IFREG | 0o4755);
+        assert_eq!(STAT.uid.get(&buf), 0);
+        assert_eq!(STAT.gid.get(&buf), 0);
+    }
+    
+    #[test]
+    fn test_overlay_statx_honours_mask() {
+        let mut buf = vec![0u8; STATX.prefix_len()];
+        Field::u32(0).set(&mut buf, STATX_TYPE | STATX_MODE);
+        STATX.mode.set(&mut buf, libc::S_IFDIR | 0o700);
+        STATX.uid.set(&mut buf, 1000);
+        
+        overlay(&STATX, &mut buf, &meta(0, 0, 0o755));
+        
+        assert_eq!(STATX.mode.get(&buf), libc::S_IFDIR | 0o755);
+        assert_eq!(STATX.uid.get(&buf), 1000);
+    }
+}
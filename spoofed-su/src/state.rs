@@ -1,4 +1,5 @@
-use crate::types::{FakeState, MinSukiError, Result};
+use crate::caps::FileCaps;
+use crate::types::{FakeMetadata, FakeState, MinSukiError, Result};
 use std::fs::{File, OpenOptions};
 use std::io::{Read, Write};
 use std::path::Path;
@@ -53,32 +54,30 @@ impl StateManager {
         Arc::clone(&self.state)
     }
     
-    pub fn chown(&self, path: std::path::PathBuf, uid: u32, gid: u32) -> Result<()> {
+    pub fn set_metadata(&self, path: std::path::PathBuf, metadata: FakeMetadata) -> Result<()> {
         let mut state = self.state.lock().unwrap();
-        state.chown(path, uid, gid);
+        state.set_metadata(path, metadata);
         drop(state);
         self.save()
     }
     
-    pub fn chmod(&self, path: std::path::PathBuf, mode: u32) -> Result<()> {
+    pub fn chown(&self, path: std::path::PathBuf, uid: u32, gid: u32) -> Result<()> {
         let mut state = self.state.lock().unwrap();
-        state.chmod(path, mode);
+        state.chown(path, uid, gid);
         drop(state);
         self.save()
     }
     
-    pub fn setuid(&self, uid: u32) -> Result<()> {
+    pub fn chmod(&self, path: std::path::PathBuf, mode: u32) -> Result<()> {
         let mut state = self.state.lock().unwrap();
-        state.effective_uid = uid;
-        state.current_uid = uid;
+        state.chmod(path, mode);
         drop(state);
         self.save()
     }
     
-    pub fn setgid(&self, gid: u32) -> Result<()> {
+    pub fn setcap(&self, path: std::path::PathBuf, capabilities: FileCaps) -> Result<()> {
         let mut state = self.state.lock().unwrap();
-        state.effective_gid = gid;
-        state.current_gid = gid;
+        state.setcap(path, capabilities);
         drop(state);
         self.save()
     }
@@ -0,0 +1,1248 @@
+// This file is @generated by scripts/gen_syscalls.py from the kernel's
+// syscall tables. Do not edit it by hand; rerun the script instead.
+
+/// Syscalls the interceptor cares about, named after their kernel entry point
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sysno {
+    Access,
+    Capget,
+    Capset,
+    Chmod,
+    Chown,
+    Chown16,
+    Creat,
+    ExitGroup,
+    Faccessat,
+    Faccessat2,
+    Fchmod,
+    Fchmodat,
+    Fchmodat2,
+    Fchown,
+    Fchown16,
+    Fchownat,
+    Fstat64,
+    Fstatat64,
+    Getegid,
+    Getegid16,
+    Geteuid,
+    Geteuid16,
+    Getgid,
+    Getgid16,
+    Getgroups,
+    Getgroups16,
+    Getresgid,
+    Getresgid16,
+    Getresuid,
+    Getresuid16,
+    Getuid,
+    Getuid16,
+    Lchown,
+    Lchown16,
+    Link,
+    Linkat,
+    Lstat64,
+    Mkdir,
+    Mkdirat,
+    Mknod,
+    Mknodat,
+    Newfstat,
+    Newfstatat,
+    Newlstat,
+    Newstat,
+    Open,
+    Openat,
+    Openat2,
+    Prctl,
+    Setfsgid,
+    Setfsgid16,
+    Setfsuid,
+    Setfsuid16,
+    Setgid,
+    Setgid16,
+    Setgroups,
+    Setgroups16,
+    Setregid,
+    Setregid16,
+    Setresgid,
+    Setresgid16,
+    Setresuid,
+    Setresuid16,
+    Setreuid,
+    Setreuid16,
+    Setuid,
+    Setuid16,
+    Stat64,
+    Statx,
+    Symlink,
+    Symlinkat,
+}
+
+#[cfg(target_arch = "aarch64")]
+impl Sysno {
+    /// Every known syscall of this architecture, ordered by number
+    pub const ALL: &'static [Sysno] = &[
+        Sysno::Mknodat,
+        Sysno::Mkdirat,
+        Sysno::Symlinkat,
+        Sysno::Linkat,
+        Sysno::Faccessat,
+        Sysno::Fchmod,
+        Sysno::Fchmodat,
+        Sysno::Fchownat,
+        Sysno::Fchown,
+        Sysno::Openat,
+        Sysno::Newfstatat,
+        Sysno::Newfstat,
+        Sysno::Capget,
+        Sysno::Capset,
+        Sysno::ExitGroup,
+        Sysno::Setregid,
+        Sysno::Setgid,
+        Sysno::Setreuid,
+        Sysno::Setuid,
+        Sysno::Setresuid,
+        Sysno::Getresuid,
+        Sysno::Setresgid,
+        Sysno::Getresgid,
+        Sysno::Setfsuid,
+        Sysno::Setfsgid,
+        Sysno::Getgroups,
+        Sysno::Setgroups,
+        Sysno::Prctl,
+        Sysno::Getuid,
+        Sysno::Geteuid,
+        Sysno::Getgid,
+        Sysno::Getegid,
+        Sysno::Statx,
+        Sysno::Openat2,
+        Sysno::Faccessat2,
+        Sysno::Fchmodat2,
+    ];
+
+    pub fn from_number(number: i64) -> Option<Self> {
+        match number {
+            33 => Some(Sysno::Mknodat),
+            34 => Some(Sysno::Mkdirat),
+            36 => Some(Sysno::Symlinkat),
+            37 => Some(Sysno::Linkat),
+            48 => Some(Sysno::Faccessat),
+            52 => Some(Sysno::Fchmod),
+            53 => Some(Sysno::Fchmodat),
+            54 => Some(Sysno::Fchownat),
+            55 => Some(Sysno::Fchown),
+            56 => Some(Sysno::Openat),
+            79 => Some(Sysno::Newfstatat),
+            80 => Some(Sysno::Newfstat),
+            90 => Some(Sysno::Capget),
+            91 => Some(Sysno::Capset),
+            94 => Some(Sysno::ExitGroup),
+            143 => Some(Sysno::Setregid),
+            144 => Some(Sysno::Setgid),
+            145 => Some(Sysno::Setreuid),
+            146 => Some(Sysno::Setuid),
+            147 => Some(Sysno::Setresuid),
+            148 => Some(Sysno::Getresuid),
+            149 => Some(Sysno::Setresgid),
+            150 => Some(Sysno::Getresgid),
+            151 => Some(Sysno::Setfsuid),
+            152 => Some(Sysno::Setfsgid),
+            158 => Some(Sysno::Getgroups),
+            159 => Some(Sysno::Setgroups),
+            167 => Some(Sysno::Prctl),
+            174 => Some(Sysno::Getuid),
+            175 => Some(Sysno::Geteuid),
+            176 => Some(Sysno::Getgid),
+            177 => Some(Sysno::Getegid),
+            291 => Some(Sysno::Statx),
+            437 => Some(Sysno::Openat2),
+            439 => Some(Sysno::Faccessat2),
+            452 => Some(Sysno::Fchmodat2),
+            _ => None,
+        }
+    }
+
+    pub fn number(self) -> Option<i64> {
+        match self {
+            Sysno::Mknodat => Some(33),
+            Sysno::Mkdirat => Some(34),
+            Sysno::Symlinkat => Some(36),
+            Sysno::Linkat => Some(37),
+            Sysno::Faccessat => Some(48),
+            Sysno::Fchmod => Some(52),
+            Sysno::Fchmodat => Some(53),
+            Sysno::Fchownat => Some(54),
+            Sysno::Fchown => Some(55),
+            Sysno::Openat => Some(56),
+            Sysno::Newfstatat => Some(79),
+            Sysno::Newfstat => Some(80),
+            Sysno::Capget => Some(90),
+            Sysno::Capset => Some(91),
+            Sysno::ExitGroup => Some(94),
+            Sysno::Setregid => Some(143),
+            Sysno::Setgid => Some(144),
+            Sysno::Setreuid => Some(145),
+            Sysno::Setuid => Some(146),
+            Sysno::Setresuid => Some(147),
+            Sysno::Getresuid => Some(148),
+            Sysno::Setresgid => Some(149),
+            Sysno::Getresgid => Some(150),
+            Sysno::Setfsuid => Some(151),
+            Sysno::Setfsgid => Some(152),
+            Sysno::Getgroups => Some(158),
+            Sysno::Setgroups => Some(159),
+            Sysno::Prctl => Some(167),
+            Sysno::Getuid => Some(174),
+            Sysno::Geteuid => Some(175),
+            Sysno::Getgid => Some(176),
+            Sysno::Getegid => Some(177),
+            Sysno::Statx => Some(291),
+            Sysno::Openat2 => Some(437),
+            Sysno::Faccessat2 => Some(439),
+            Sysno::Fchmodat2 => Some(452),
+            _ => None,
+        }
+    }
+
+    /// The syscall's name in this architecture's table
+    pub fn name(self) -> &'static str {
+        match self {
+            Sysno::Mknodat => "mknodat",
+            Sysno::Mkdirat => "mkdirat",
+            Sysno::Symlinkat => "symlinkat",
+            Sysno::Linkat => "linkat",
+            Sysno::Faccessat => "faccessat",
+            Sysno::Fchmod => "fchmod",
+            Sysno::Fchmodat => "fchmodat",
+            Sysno::Fchownat => "fchownat",
+            Sysno::Fchown => "fchown",
+            Sysno::Openat => "openat",
+            Sysno::Newfstatat => "newfstatat",
+            Sysno::Newfstat => "fstat",
+            Sysno::Capget => "capget",
+            Sysno::Capset => "capset",
+            Sysno::ExitGroup => "exit_group",
+            Sysno::Setregid => "setregid",
+            Sysno::Setgid => "setgid",
+            Sysno::Setreuid => "setreuid",
+            Sysno::Setuid => "setuid",
+            Sysno::Setresuid => "setresuid",
+            Sysno::Getresuid => "getresuid",
+            Sysno::Setresgid => "setresgid",
+            Sysno::Getresgid => "getresgid",
+            Sysno::Setfsuid => "setfsuid",
+            Sysno::Setfsgid => "setfsgid",
+            Sysno::Getgroups => "getgroups",
+            Sysno::Setgroups => "setgroups",
+            Sysno::Prctl => "prctl",
+            Sysno::Getuid => "getuid",
+            Sysno::Geteuid => "geteuid",
+            Sysno::Getgid => "getgid",
+            Sysno::Getegid => "getegid",
+            Sysno::Statx => "statx",
+            Sysno::Openat2 => "openat2",
+            Sysno::Faccessat2 => "faccessat2",
+            Sysno::Fchmodat2 => "fchmodat2",
+            _ => "unknown",
+        }
+    }
+}
+
+#[cfg(target_arch = "arm")]
+impl Sysno {
+    /// Every known syscall of this architecture, ordered by number
+    pub const ALL: &'static [Sysno] = &[
+        Sysno::Open,
+        Sysno::Creat,
+        Sysno::Link,
+        Sysno::Mknod,
+        Sysno::Chmod,
+        Sysno::Lchown16,
+        Sysno::Setuid16,
+        Sysno::Getuid16,
+        Sysno::Access,
+        Sysno::Mkdir,
+        Sysno::Setgid16,
+        Sysno::Getgid16,
+        Sysno::Geteuid16,
+        Sysno::Getegid16,
+        Sysno::Setreuid16,
+        Sysno::Setregid16,
+        Sysno::Getgroups16,
+        Sysno::Setgroups16,
+        Sysno::Symlink,
+        Sysno::Fchmod,
+        Sysno::Fchown16,
+        Sysno::Newstat,
+        Sysno::Newlstat,
+        Sysno::Newfstat,
+        Sysno::Setfsuid16,
+        Sysno::Setfsgid16,
+        Sysno::Setresuid16,
+        Sysno::Getresuid16,
+        Sysno::Setresgid16,
+        Sysno::Getresgid16,
+        Sysno::Prctl,
+        Sysno::Chown16,
+        Sysno::Capget,
+        Sysno::Capset,
+        Sysno::Stat64,
+        Sysno::Lstat64,
+        Sysno::Fstat64,
+        Sysno::Lchown,
+        Sysno::Getuid,
+        Sysno::Getgid,
+        Sysno::Geteuid,
+        Sysno::Getegid,
+        Sysno::Setreuid,
+        Sysno::Setregid,
+        Sysno::Getgroups,
+        Sysno::Setgroups,
+        Sysno::Fchown,
+        Sysno::Setresuid,
+        Sysno::Getresuid,
+        Sysno::Setresgid,
+        Sysno::Getresgid,
+        Sysno::Chown,
+        Sysno::Setuid,
+        Sysno::Setgid,
+        Sysno::Setfsuid,
+        Sysno::Setfsgid,
+        Sysno::ExitGroup,
+        Sysno::Openat,
+        Sysno::Mkdirat,
+        Sysno::Mknodat,
+        Sysno::Fchownat,
+        Sysno::Fstatat64,
+        Sysno::Linkat,
+        Sysno::Symlinkat,
+        Sysno::Fchmodat,
+        Sysno::Faccessat,
+        Sysno::Statx,
+        Sysno::Openat2,
+        Sysno::Faccessat2,
+        Sysno::Fchmodat2,
+    ];
+
+    pub fn from_number(number: i64) -> Option<Self> {
+        match number {
+            5 => Some(Sysno::Open),
+            8 => Some(Sysno::Creat),
+            9 => Some(Sysno::Link),
+            14 => Some(Sysno::Mknod),
+            15 => Some(Sysno::Chmod),
+            16 => Some(Sysno::Lchown16),
+            23 => Some(Sysno::Setuid16),
+            24 => Some(Sysno::Getuid16),
+            33 => Some(Sysno::Access),
+            39 => Some(Sysno::Mkdir),
+            46 => Some(Sysno::Setgid16),
+            47 => Some(Sysno::Getgid16),
+            49 => Some(Sysno::Geteuid16),
+            50 => Some(Sysno::Getegid16),
+            70 => Some(Sysno::Setreuid16),
+            71 => Some(Sysno::Setregid16),
+            80 => Some(Sysno::Getgroups16),
+            81 => Some(Sysno::Setgroups16),
+            83 => Some(Sysno::Symlink),
+            94 => Some(Sysno::Fchmod),
+            95 => Some(Sysno::Fchown16),
+            106 => Some(Sysno::Newstat),
+            107 => Some(Sysno::Newlstat),
+            108 => Some(Sysno::Newfstat),
+            138 => Some(Sysno::Setfsuid16),
+            139 => Some(Sysno::Setfsgid16),
+            164 => Some(Sysno::Setresuid16),
+            165 => Some(Sysno::Getresuid16),
+            170 => Some(Sysno::Setresgid16),
+            171 => Some(Sysno::Getresgid16),
+            172 => Some(Sysno::Prctl),
+            182 => Some(Sysno::Chown16),
+            184 => Some(Sysno::Capget),
+            185 => Some(Sysno::Capset),
+            195 => Some(Sysno::Stat64),
+            196 => Some(Sysno::Lstat64),
+            197 => Some(Sysno::Fstat64),
+            198 => Some(Sysno::Lchown),
+            199 => Some(Sysno::Getuid),
+            200 => Some(Sysno::Getgid),
+            201 => Some(Sysno::Geteuid),
+            202 => Some(Sysno::Getegid),
+            203 => Some(Sysno::Setreuid),
+            204 => Some(Sysno::Setregid),
+            205 => Some(Sysno::Getgroups),
+            206 => Some(Sysno::Setgroups),
+            207 => Some(Sysno::Fchown),
+            208 => Some(Sysno::Setresuid),
+            209 => Some(Sysno::Getresuid),
+            210 => Some(Sysno::Setresgid),
+            211 => Some(Sysno::Getresgid),
+            212 => Some(Sysno::Chown),
+            213 => Some(Sysno::Setuid),
+            214 => Some(Sysno::Setgid),
+            215 => Some(Sysno::Setfsuid),
+            216 => Some(Sysno::Setfsgid),
+            248 => Some(Sysno::ExitGroup),
+            322 => Some(Sysno::Openat),
+            323 => Some(Sysno::Mkdirat),
+            324 => Some(Sysno::Mknodat),
+            325 => Some(Sysno::Fchownat),
+            327 => Some(Sysno::Fstatat64),
+            330 => Some(Sysno::Linkat),
+            331 => Some(Sysno::Symlinkat),
+            333 => Some(Sysno::Fchmodat),
+            334 => Some(Sysno::Faccessat),
+            397 => Some(Sysno::Statx),
+            437 => Some(Sysno::Openat2),
+            439 => Some(Sysno::Faccessat2),
+            452 => Some(Sysno::Fchmodat2),
+            _ => None,
+        }
+    }
+
+    pub fn number(self) -> Option<i64> {
+        match self {
+            Sysno::Open => Some(5),
+            Sysno::Creat => Some(8),
+            Sysno::Link => Some(9),
+            Sysno::Mknod => Some(14),
+            Sysno::Chmod => Some(15),
+            Sysno::Lchown16 => Some(16),
+            Sysno::Setuid16 => Some(23),
+            Sysno::Getuid16 => Some(24),
+            Sysno::Access => Some(33),
+            Sysno::Mkdir => Some(39),
+            Sysno::Setgid16 => Some(46),
+            Sysno::Getgid16 => Some(47),
+            Sysno::Geteuid16 => Some(49),
+            Sysno::Getegid16 => Some(50),
+            Sysno::Setreuid16 => Some(70),
+            Sysno::Setregid16 => Some(71),
+            Sysno::Getgroups16 => Some(80),
+            Sysno::Setgroups16 => Some(81),
+            Sysno::Symlink => Some(83),
+            Sysno::Fchmod => Some(94),
+            Sysno::Fchown16 => Some(95),
+            Sysno::Newstat => Some(106),
+            Sysno::Newlstat => Some(107),
+            Sysno::Newfstat => Some(108),
+            Sysno::Setfsuid16 => Some(138),
+            Sysno::Setfsgid16 => Some(139),
+            Sysno::Setresuid16 => Some(164),
+            Sysno::Getresuid16 => Some(165),
+            Sysno::Setresgid16 => Some(170),
+            Sysno::Getresgid16 => Some(171),
+            Sysno::Prctl => Some(172),
+            Sysno::Chown16 => Some(182),
+            Sysno::Capget => Some(184),
+            Sysno::Capset => Some(185),
+            Sysno::Stat64 => Some(195),
+            Sysno::Lstat64 => Some(196),
+            Sysno::Fstat64 => Some(197),
+            Sysno::Lchown => Some(198),
+            Sysno::Getuid => Some(199),
+            Sysno::Getgid => Some(200),
+            Sysno::Geteuid => Some(201),
+            Sysno::Getegid => Some(202),
+            Sysno::Setreuid => Some(203),
+            Sysno::Setregid => Some(204),
+            Sysno::Getgroups => Some(205),
+            Sysno::Setgroups => Some(206),
+            Sysno::Fchown => Some(207),
+            Sysno::Setresuid => Some(208),
+            Sysno::Getresuid => Some(209),
+            Sysno::Setresgid => Some(210),
+            Sysno::Getresgid => Some(211),
+            Sysno::Chown => Some(212),
+            Sysno::Setuid => Some(213),
+            Sysno::Setgid => Some(214),
+            Sysno::Setfsuid => Some(215),
+            Sysno::Setfsgid => Some(216),
+            Sysno::ExitGroup => Some(248),
+            Sysno::Openat => Some(322),
+            Sysno::Mkdirat => Some(323),
+            Sysno::Mknodat => Some(324),
+            Sysno::Fchownat => Some(325),
+            Sysno::Fstatat64 => Some(327),
+            Sysno::Linkat => Some(330),
+            Sysno::Symlinkat => Some(331),
+            Sysno::Fchmodat => Some(333),
+            Sysno::Faccessat => Some(334),
+            Sysno::Statx => Some(397),
+            Sysno::Openat2 => Some(437),
+            Sysno::Faccessat2 => Some(439),
+            Sysno::Fchmodat2 => Some(452),
+            _ => None,
+        }
+    }
+
+    /// The syscall's name in this architecture's table
+    pub fn name(self) -> &'static str {
+        match self {
+            Sysno::Open => "open",
+            Sysno::Creat => "creat",
+            Sysno::Link => "link",
+            Sysno::Mknod => "mknod",
+            Sysno::Chmod => "chmod",
+            Sysno::Lchown16 => "lchown",
+            Sysno::Setuid16 => "setuid",
+            Sysno::Getuid16 => "getuid",
+            Sysno::Access => "access",
+            Sysno::Mkdir => "mkdir",
+            Sysno::Setgid16 => "setgid",
+            Sysno::Getgid16 => "getgid",
+            Sysno::Geteuid16 => "geteuid",
+            Sysno::Getegid16 => "getegid",
+            Sysno::Setreuid16 => "setreuid",
+            Sysno::Setregid16 => "setregid",
+            Sysno::Getgroups16 => "getgroups",
+            Sysno::Setgroups16 => "setgroups",
+            Sysno::Symlink => "symlink",
+            Sysno::Fchmod => "fchmod",
+            Sysno::Fchown16 => "fchown",
+            Sysno::Newstat => "stat",
+            Sysno::Newlstat => "lstat",
+            Sysno::Newfstat => "fstat",
+            Sysno::Setfsuid16 => "setfsuid",
+            Sysno::Setfsgid16 => "setfsgid",
+            Sysno::Setresuid16 => "setresuid",
+            Sysno::Getresuid16 => "getresuid",
+            Sysno::Setresgid16 => "setresgid",
+            Sysno::Getresgid16 => "getresgid",
+            Sysno::Prctl => "prctl",
+            Sysno::Chown16 => "chown",
+            Sysno::Capget => "capget",
+            Sysno::Capset => "capset",
+            Sysno::Stat64 => "stat64",
+            Sysno::Lstat64 => "lstat64",
+            Sysno::Fstat64 => "fstat64",
+            Sysno::Lchown => "lchown32",
+            Sysno::Getuid => "getuid32",
+            Sysno::Getgid => "getgid32",
+            Sysno::Geteuid => "geteuid32",
+            Sysno::Getegid => "getegid32",
+            Sysno::Setreuid => "setreuid32",
+            Sysno::Setregid => "setregid32",
+            Sysno::Getgroups => "getgroups32",
+            Sysno::Setgroups => "setgroups32",
+            Sysno::Fchown => "fchown32",
+            Sysno::Setresuid => "setresuid32",
+            Sysno::Getresuid => "getresuid32",
+            Sysno::Setresgid => "setresgid32",
+            Sysno::Getresgid => "getresgid32",
+            Sysno::Chown => "chown32",
+            Sysno::Setuid => "setuid32",
+            Sysno::Setgid => "setgid32",
+            Sysno::Setfsuid => "setfsuid32",
+            Sysno::Setfsgid => "setfsgid32",
+            Sysno::ExitGroup => "exit_group",
+            Sysno::Openat => "openat",
+            Sysno::Mkdirat => "mkdirat",
+            Sysno::Mknodat => "mknodat",
+            Sysno::Fchownat => "fchownat",
+            Sysno::Fstatat64 => "fstatat64",
+            Sysno::Linkat => "linkat",
+            Sysno::Symlinkat => "symlinkat",
+            Sysno::Fchmodat => "fchmodat",
+            Sysno::Faccessat => "faccessat",
+            Sysno::Statx => "statx",
+            Sysno::Openat2 => "openat2",
+            Sysno::Faccessat2 => "faccessat2",
+            Sysno::Fchmodat2 => "fchmodat2",
+            _ => "unknown",
+        }
+    }
+}
+
+#[cfg(target_arch = "riscv64")]
+impl Sysno {
+    /// Every known syscall of this architecture, ordered by number
+    pub const ALL: &'static [Sysno] = &[
+        Sysno::Mknodat,
+        Sysno::Mkdirat,
+        Sysno::Symlinkat,
+        Sysno::Linkat,
+        Sysno::Faccessat,
+        Sysno::Fchmod,
+        Sysno::Fchmodat,
+        Sysno::Fchownat,
+        Sysno::Fchown,
+        Sysno::Openat,
+        Sysno::Newfstatat,
+        Sysno::Newfstat,
+        Sysno::Capget,
+        Sysno::Capset,
+        Sysno::ExitGroup,
+        Sysno::Setregid,
+        Sysno::Setgid,
+        Sysno::Setreuid,
+        Sysno::Setuid,
+        Sysno::Setresuid,
+        Sysno::Getresuid,
+        Sysno::Setresgid,
+        Sysno::Getresgid,
+        Sysno::Setfsuid,
+        Sysno::Setfsgid,
+        Sysno::Getgroups,
+        Sysno::Setgroups,
+        Sysno::Prctl,
+        Sysno::Getuid,
+        Sysno::Geteuid,
+        Sysno::Getgid,
+        Sysno::Getegid,
+        Sysno::Statx,
+        Sysno::Openat2,
+        Sysno::Faccessat2,
+        Sysno::Fchmodat2,
+    ];
+
+    pub fn from_number(number: i64) -> Option<Self> {
+        match number {
+            33 => Some(Sysno::Mknodat),
+            34 => Some(Sysno::Mkdirat),
+            36 => Some(Sysno::Symlinkat),
+            37 => Some(Sysno::Linkat),
+            48 => Some(Sysno::Faccessat),
+            52 => Some(Sysno::Fchmod),
+            53 => Some(Sysno::Fchmodat),
+            54 => Some(Sysno::Fchownat),
+            55 => Some(Sysno::Fchown),
+            56 => Some(Sysno::Openat),
+            79 => Some(Sysno::Newfstatat),
+            80 => Some(Sysno::Newfstat),
+            90 => Some(Sysno::Capget),
+            91 => Some(Sysno::Capset),
+            94 => Some(Sysno::ExitGroup),
+            143 => Some(Sysno::Setregid),
+            144 => Some(Sysno::Setgid),
+            145 => Some(Sysno::Setreuid),
+            146 => Some(Sysno::Setuid),
+            147 => Some(Sysno::Setresuid),
+            148 => Some(Sysno::Getresuid),
+            149 => Some(Sysno::Setresgid),
+            150 => Some(Sysno::Getresgid),
+            151 => Some(Sysno::Setfsuid),
+            152 => Some(Sysno::Setfsgid),
+            158 => Some(Sysno::Getgroups),
+            159 => Some(Sysno::Setgroups),
+            167 => Some(Sysno::Prctl),
+            174 => Some(Sysno::Getuid),
+            175 => Some(Sysno::Geteuid),
+            176 => Some(Sysno::Getgid),
+            177 => Some(Sysno::Getegid),
+            291 => Some(Sysno::Statx),
+            437 => Some(Sysno::Openat2),
+            439 => Some(Sysno::Faccessat2),
+            452 => Some(Sysno::Fchmodat2),
+            _ => None,
+        }
+    }
+
+    pub fn number(self) -> Option<i64> {
+        match self {
+            Sysno::Mknodat => Some(33),
+            Sysno::Mkdirat => Some(34),
+            Sysno::Symlinkat => Some(36),
+            Sysno::Linkat => Some(37),
+            Sysno::Faccessat => Some(48),
+            Sysno::Fchmod => Some(52),
+            Sysno::Fchmodat => Some(53),
+            Sysno::Fchownat => Some(54),
+            Sysno::Fchown => Some(55),
+            Sysno::Openat => Some(56),
+            Sysno::Newfstatat => Some(79),
+            Sysno::Newfstat => Some(80),
+            Sysno::Capget => Some(90),
+            Sysno::Capset => Some(91),
+            Sysno::ExitGroup => Some(94),
+            Sysno::Setregid => Some(143),
+            Sysno::Setgid => Some(144),
+            Sysno::Setreuid => Some(145),
+            Sysno::Setuid => Some(146),
+            Sysno::Setresuid => Some(147),
+            Sysno::Getresuid => Some(148),
+            Sysno::Setresgid => Some(149),
+            Sysno::Getresgid => Some(150),
+            Sysno::Setfsuid => Some(151),
+            Sysno::Setfsgid => Some(152),
+            Sysno::Getgroups => Some(158),
+            Sysno::Setgroups => Some(159),
+            Sysno::Prctl => Some(167),
+            Sysno::Getuid => Some(174),
+            Sysno::Geteuid => Some(175),
+            Sysno::Getgid => Some(176),
+            Sysno::Getegid => Some(177),
+            Sysno::Statx => Some(291),
+            Sysno::Openat2 => Some(437),
+            Sysno::Faccessat2 => Some(439),
+            Sysno::Fchmodat2 => Some(452),
+            _ => None,
+        }
+    }
+
+    /// The syscall's name in this architecture's table
+    pub fn name(self) -> &'static str {
+        match self {
+            Sysno::Mknodat => "mknodat",
+            Sysno::Mkdirat => "mkdirat",
+            Sysno::Symlinkat => "symlinkat",
+            Sysno::Linkat => "linkat",
+            Sysno::Faccessat => "faccessat",
+            Sysno::Fchmod => "fchmod",
+            Sysno::Fchmodat => "fchmodat",
+            Sysno::Fchownat => "fchownat",
+            Sysno::Fchown => "fchown",
+            Sysno::Openat => "openat",
+            Sysno::Newfstatat => "newfstatat",
+            Sysno::Newfstat => "fstat",
+            Sysno::Capget => "capget",
+            Sysno::Capset => "capset",
+            Sysno::ExitGroup => "exit_group",
+            Sysno::Setregid => "setregid",
+            Sysno::Setgid => "setgid",
+            Sysno::Setreuid => "setreuid",
+            Sysno::Setuid => "setuid",
+            Sysno::Setresuid => "setresuid",
+            Sysno::Getresuid => "getresuid",
+            Sysno::Setresgid => "setresgid",
+            Sysno::Getresgid => "getresgid",
+            Sysno::Setfsuid => "setfsuid",
+            Sysno::Setfsgid => "setfsgid",
+            Sysno::Getgroups => "getgroups",
+            Sysno::Setgroups => "setgroups",
+            Sysno::Prctl => "prctl",
+            Sysno::Getuid => "getuid",
+            Sysno::Geteuid => "geteuid",
+            Sysno::Getgid => "getgid",
+            Sysno::Getegid => "getegid",
+            Sysno::Statx => "statx",
+            Sysno::Openat2 => "openat2",
+            Sysno::Faccessat2 => "faccessat2",
+            Sysno::Fchmodat2 => "fchmodat2",
+            _ => "unknown",
+        }
+    }
+}
+
+#[cfg(target_arch = "x86")]
+impl Sysno {
+    /// Every known syscall of this architecture, ordered by number
+    pub const ALL: &'static [Sysno] = &[
+        Sysno::Open,
+        Sysno::Creat,
+        Sysno::Link,
+        Sysno::Mknod,
+        Sysno::Chmod,
+        Sysno::Lchown16,
+        Sysno::Setuid16,
+        Sysno::Getuid16,
+        Sysno::Access,
+        Sysno::Mkdir,
+        Sysno::Setgid16,
+        Sysno::Getgid16,
+        Sysno::Geteuid16,
+        Sysno::Getegid16,
+        Sysno::Setreuid16,
+        Sysno::Setregid16,
+        Sysno::Getgroups16,
+        Sysno::Setgroups16,
+        Sysno::Symlink,
+        Sysno::Fchmod,
+        Sysno::Fchown16,
+        Sysno::Newstat,
+        Sysno::Newlstat,
+        Sysno::Newfstat,
+        Sysno::Setfsuid16,
+        Sysno::Setfsgid16,
+        Sysno::Setresuid16,
+        Sysno::Getresuid16,
+        Sysno::Setresgid16,
+        Sysno::Getresgid16,
+        Sysno::Prctl,
+        Sysno::Chown16,
+        Sysno::Capget,
+        Sysno::Capset,
+        Sysno::Stat64,
+        Sysno::Lstat64,
+        Sysno::Fstat64,
+        Sysno::Lchown,
+        Sysno::Getuid,
+        Sysno::Getgid,
+        Sysno::Geteuid,
+        Sysno::Getegid,
+        Sysno::Setreuid,
+        Sysno::Setregid,
+        Sysno::Getgroups,
+        Sysno::Setgroups,
+        Sysno::Fchown,
+        Sysno::Setresuid,
+        Sysno::Getresuid,
+        Sysno::Setresgid,
+        Sysno::Getresgid,
+        Sysno::Chown,
+        Sysno::Setuid,
+        Sysno::Setgid,
+        Sysno::Setfsuid,
+        Sysno::Setfsgid,
+        Sysno::ExitGroup,
+        Sysno::Openat,
+        Sysno::Mkdirat,
+        Sysno::Mknodat,
+        Sysno::Fchownat,
+        Sysno::Fstatat64,
+        Sysno::Linkat,
+        Sysno::Symlinkat,
+        Sysno::Fchmodat,
+        Sysno::Faccessat,
+        Sysno::Statx,
+        Sysno::Openat2,
+        Sysno::Faccessat2,
+        Sysno::Fchmodat2,
+    ];
+
+    pub fn from_number(number: i64) -> Option<Self> {
+        match number {
+            5 => Some(Sysno::Open),
+            8 => Some(Sysno::Creat),
+            9 => Some(Sysno::Link),
+            14 => Some(Sysno::Mknod),
+            15 => Some(Sysno::Chmod),
+            16 => Some(Sysno::Lchown16),
+            23 => Some(Sysno::Setuid16),
+            24 => Some(Sysno::Getuid16),
+            33 => Some(Sysno::Access),
+            39 => Some(Sysno::Mkdir),
+            46 => Some(Sysno::Setgid16),
+            47 => Some(Sysno::Getgid16),
+            49 => Some(Sysno::Geteuid16),
+            50 => Some(Sysno::Getegid16),
+            70 => Some(Sysno::Setreuid16),
+            71 => Some(Sysno::Setregid16),
+            80 => Some(Sysno::Getgroups16),
+            81 => Some(Sysno::Setgroups16),
+            83 => Some(Sysno::Symlink),
+            94 => Some(Sysno::Fchmod),
+            95 => Some(Sysno::Fchown16),
+            106 => Some(Sysno::Newstat),
+            107 => Some(Sysno::Newlstat),
+            108 => Some(Sysno::Newfstat),
+            138 => Some(Sysno::Setfsuid16),
+            139 => Some(Sysno::Setfsgid16),
+            164 => Some(Sysno::Setresuid16),
+            165 => Some(Sysno::Getresuid16),
+            170 => Some(Sysno::Setresgid16),
+            171 => Some(Sysno::Getresgid16),
+            172 => Some(Sysno::Prctl),
+            182 => Some(Sysno::Chown16),
+            184 => Some(Sysno::Capget),
+            185 => Some(Sysno::Capset),
+            195 => Some(Sysno::Stat64),
+            196 => Some(Sysno::Lstat64),
+            197 => Some(Sysno::Fstat64),
+            198 => Some(Sysno::Lchown),
+            199 => Some(Sysno::Getuid),
+            200 => Some(Sysno::Getgid),
+            201 => Some(Sysno::Geteuid),
+            202 => Some(Sysno::Getegid),
+            203 => Some(Sysno::Setreuid),
+            204 => Some(Sysno::Setregid),
+            205 => Some(Sysno::Getgroups),
+            206 => Some(Sysno::Setgroups),
+            207 => Some(Sysno::Fchown),
+            208 => Some(Sysno::Setresuid),
+            209 => Some(Sysno::Getresuid),
+            210 => Some(Sysno::Setresgid),
+            211 => Some(Sysno::Getresgid),
+            212 => Some(Sysno::Chown),
+            213 => Some(Sysno::Setuid),
+            214 => Some(Sysno::Setgid),
+            215 => Some(Sysno::Setfsuid),
+            216 => Some(Sysno::Setfsgid),
+            252 => Some(Sysno::ExitGroup),
+            295 => Some(Sysno::Openat),
+            296 => Some(Sysno::Mkdirat),
+            297 => Some(Sysno::Mknodat),
+            298 => Some(Sysno::Fchownat),
+            300 => Some(Sysno::Fstatat64),
+            303 => Some(Sysno::Linkat),
+            304 => Some(Sysno::Symlinkat),
+            306 => Some(Sysno::Fchmodat),
+            307 => Some(Sysno::Faccessat),
+            383 => Some(Sysno::Statx),
+            437 => Some(Sysno::Openat2),
+            439 => Some(Sysno::Faccessat2),
+            452 => Some(Sysno::Fchmodat2),
+            _ => None,
+        }
+    }
+
+    pub fn number(self) -> Option<i64> {
+        match self {
+            Sysno::Open => Some(5),
+            Sysno::Creat => Some(8),
+            Sysno::Link => Some(9),
+            Sysno::Mknod => Some(14),
+            Sysno::Chmod => Some(15),
+            Sysno::Lchown16 => Some(16),
+            Sysno::Setuid16 => Some(23),
+            Sysno::Getuid16 => Some(24),
+            Sysno::Access => Some(33),
+            Sysno::Mkdir => Some(39),
+            Sysno::Setgid16 => Some(46),
+            Sysno::Getgid16 => Some(47),
+            Sysno::Geteuid16 => Some(49),
+            Sysno::Getegid16 => Some(50),
+            Sysno::Setreuid16 => Some(70),
+            Sysno::Setregid16 => Some(71),
+            Sysno::Getgroups16 => Some(80),
+            Sysno::Setgroups16 => Some(81),
+            Sysno::Symlink => Some(83),
+            Sysno::Fchmod => Some(94),
+            Sysno::Fchown16 => Some(95),
+            Sysno::Newstat => Some(106),
+            Sysno::Newlstat => Some(107),
+            Sysno::Newfstat => Some(108),
+            Sysno::Setfsuid16 => Some(138),
+            Sysno::Setfsgid16 => Some(139),
+            Sysno::Setresuid16 => Some(164),
+            Sysno::Getresuid16 => Some(165),
+            Sysno::Setresgid16 => Some(170),
+            Sysno::Getresgid16 => Some(171),
+            Sysno::Prctl => Some(172),
+            Sysno::Chown16 => Some(182),
+            Sysno::Capget => Some(184),
+            Sysno::Capset => Some(185),
+            Sysno::Stat64 => Some(195),
+            Sysno::Lstat64 => Some(196),
+            Sysno::Fstat64 => Some(197),
+            Sysno::Lchown => Some(198),
+            Sysno::Getuid => Some(199),
+            Sysno::Getgid => Some(200),
+            Sysno::Geteuid => Some(201),
+            Sysno::Getegid => Some(202),
+            Sysno::Setreuid => Some(203),
+            Sysno::Setregid => Some(204),
+            Sysno::Getgroups => Some(205),
+            Sysno::Setgroups => Some(206),
+            Sysno::Fchown => Some(207),
+            Sysno::Setresuid => Some(208),
+            Sysno::Getresuid => Some(209),
+            Sysno::Setresgid => Some(210),
+            Sysno::Getresgid => Some(211),
+            Sysno::Chown => Some(212),
+            Sysno::Setuid => Some(213),
+            Sysno::Setgid => Some(214),
+            Sysno::Setfsuid => Some(215),
+            Sysno::Setfsgid => Some(216),
+            Sysno::ExitGroup => Some(252),
+            Sysno::Openat => Some(295),
+            Sysno::Mkdirat => Some(296),
+            Sysno::Mknodat => Some(297),
+            Sysno::Fchownat => Some(298),
+            Sysno::Fstatat64 => Some(300),
+            Sysno::Linkat => Some(303),
+            Sysno::Symlinkat => Some(304),
+            Sysno::Fchmodat => Some(306),
+            Sysno::Faccessat => Some(307),
+            Sysno::Statx => Some(383),
+            Sysno::Openat2 => Some(437),
+            Sysno::Faccessat2 => Some(439),
+            Sysno::Fchmodat2 => Some(452),
+            _ => None,
+        }
+    }
+
+    /// The syscall's name in this architecture's table
+    pub fn name(self) -> &'static str {
+        match self {
+            Sysno::Open => "open",
+            Sysno::Creat => "creat",
+            Sysno::Link => "link",
+            Sysno::Mknod => "mknod",
+            Sysno::Chmod => "chmod",
+            Sysno::Lchown16 => "lchown",
+            Sysno::Setuid16 => "setuid",
+            Sysno::Getuid16 => "getuid",
+            Sysno::Access => "access",
+            Sysno::Mkdir => "mkdir",
+            Sysno::Setgid16 => "setgid",
+            Sysno::Getgid16 => "getgid",
+            Sysno::Geteuid16 => "geteuid",
+            Sysno::Getegid16 => "getegid",
+            Sysno::Setreuid16 => "setreuid",
+            Sysno::Setregid16 => "setregid",
+            Sysno::Getgroups16 => "getgroups",
+            Sysno::Setgroups16 => "setgroups",
+            Sysno::Symlink => "symlink",
+            Sysno::Fchmod => "fchmod",
+            Sysno::Fchown16 => "fchown",
+            Sysno::Newstat => "stat",
+            Sysno::Newlstat => "lstat",
+            Sysno::Newfstat => "fstat",
+            Sysno::Setfsuid16 => "setfsuid",
+            Sysno::Setfsgid16 => "setfsgid",
+            Sysno::Setresuid16 => "setresuid",
+            Sysno::Getresuid16 => "getresuid",
+            Sysno::Setresgid16 => "setresgid",
+            Sysno::Getresgid16 => "getresgid",
+            Sysno::Prctl => "prctl",
+            Sysno::Chown16 => "chown",
+            Sysno::Capget => "capget",
+            Sysno::Capset => "capset",
+            Sysno::Stat64 => "stat64",
+            Sysno::Lstat64 => "lstat64",
+            Sysno::Fstat64 => "fstat64",
+            Sysno::Lchown => "lchown32",
+            Sysno::Getuid => "getuid32",
+            Sysno::Getgid => "getgid32",
+            Sysno::Geteuid => "geteuid32",
+            Sysno::Getegid => "getegid32",
+            Sysno::Setreuid => "setreuid32",
+            Sysno::Setregid => "setregid32",
+            Sysno::Getgroups => "getgroups32",
+            Sysno::Setgroups => "setgroups32",
+            Sysno::Fchown => "fchown32",
+            Sysno::Setresuid => "setresuid32",
+            Sysno::Getresuid => "getresuid32",
+            Sysno::Setresgid => "setresgid32",
+            Sysno::Getresgid => "getresgid32",
+            Sysno::Chown => "chown32",
+            Sysno::Setuid => "setuid32",
+            Sysno::Setgid => "setgid32",
+            Sysno::Setfsuid => "setfsuid32",
+            Sysno::Setfsgid => "setfsgid32",
+            Sysno::ExitGroup => "exit_group",
+            Sysno::Openat => "openat",
+            Sysno::Mkdirat => "mkdirat",
+            Sysno::Mknodat => "mknodat",
+            Sysno::Fchownat => "fchownat",
+            Sysno::Fstatat64 => "fstatat64",
+            Sysno::Linkat => "linkat",
+            Sysno::Symlinkat => "symlinkat",
+            Sysno::Fchmodat => "fchmodat",
+            Sysno::Faccessat => "faccessat",
+            Sysno::Statx => "statx",
+            Sysno::Openat2 => "openat2",
+            Sysno::Faccessat2 => "faccessat2",
+            Sysno::Fchmodat2 => "fchmodat2",
+            _ => "unknown",
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+impl Sysno {
+    /// Every known syscall of this architecture, ordered by number
+    pub const ALL: &'static [Sysno] = &[
+        Sysno::Open,
+        Sysno::Newstat,
+        Sysno::Newfstat,
+        Sysno::Newlstat,
+        Sysno::Access,
+        Sysno::Mkdir,
+        Sysno::Creat,
+        Sysno::Link,
+        Sysno::Symlink,
+        Sysno::Chmod,
+        Sysno::Fchmod,
+        Sysno::Chown,
+        Sysno::Fchown,
+        Sysno::Lchown,
+        Sysno::Getuid,
+        Sysno::Getgid,
+        Sysno::Setuid,
+        Sysno::Setgid,
+        Sysno::Geteuid,
+        Sysno::Getegid,
+        Sysno::Setreuid,
+        Sysno::Setregid,
+        Sysno::Getgroups,
+        Sysno::Setgroups,
+        Sysno::Setresuid,
+        Sysno::Getresuid,
+        Sysno::Setresgid,
+        Sysno::Getresgid,
+        Sysno::Setfsuid,
+        Sysno::Setfsgid,
+        Sysno::Capget,
+        Sysno::Capset,
+        Sysno::Mknod,
+        Sysno::Prctl,
+        Sysno::ExitGroup,
+        Sysno::Openat,
+        Sysno::Mkdirat,
+        Sysno::Mknodat,
+        Sysno::Fchownat,
+        Sysno::Newfstatat,
+        Sysno::Linkat,
+        Sysno::Symlinkat,
+        Sysno::Fchmodat,
+        Sysno::Faccessat,
+        Sysno::Statx,
+        Sysno::Openat2,
+        Sysno::Faccessat2,
+        Sysno::Fchmodat2,
+    ];
+
+    pub fn from_number(number: i64) -> Option<Self> {
+        match number {
+            2 => Some(Sysno::Open),
+            4 => Some(Sysno::Newstat),
+            5 => Some(Sysno::Newfstat),
+            6 => Some(Sysno::Newlstat),
+            21 => Some(Sysno::Access),
+            83 => Some(Sysno::Mkdir),
+            85 => Some(Sysno::Creat),
+            86 => Some(Sysno::Link),
+            88 => Some(Sysno::Symlink),
+            90 => Some(Sysno::Chmod),
+            91 => Some(Sysno::Fchmod),
+            92 => Some(Sysno::Chown),
+            93 => Some(Sysno::Fchown),
+            94 => Some(Sysno::Lchown),
+            102 => Some(Sysno::Getuid),
+            104 => Some(Sysno::Getgid),
+            105 => Some(Sysno::Setuid),
+            106 => Some(Sysno::Setgid),
+            107 => Some(Sysno::Geteuid),
+            108 => Some(Sysno::Getegid),
+            113 => Some(Sysno::Setreuid),
+            114 => Some(Sysno::Setregid),
+            115 => Some(Sysno::Getgroups),
+            116 => Some(Sysno::Setgroups),
+            117 => Some(Sysno::Setresuid),
+            118 => Some(Sysno::Getresuid),
+            119 => Some(Sysno::Setresgid),
+            120 => Some(Sysno::Getresgid),
+            122 => Some(Sysno::Setfsuid),
+            123 => Some(Sysno::Setfsgid),
+            125 => Some(Sysno::Capget),
+            126 => Some(Sysno::Capset),
+            133 => Some(Sysno::Mknod),
+            157 => Some(Sysno::Prctl),
+            231 => Some(Sysno::ExitGroup),
+            257 => Some(Sysno::Openat),
+            258 => Some(Sysno::Mkdirat),
+            259 => Some(Sysno::Mknodat),
+            260 => Some(Sysno::Fchownat),
+            262 => Some(Sysno::Newfstatat),
+            265 => Some(Sysno::Linkat),
+            266 => Some(Sysno::Symlinkat),
+            268 => Some(Sysno::Fchmodat),
+            269 => Some(Sysno::Faccessat),
+            332 => Some(Sysno::Statx),
+            437 => Some(Sysno::Openat2),
+            439 => Some(Sysno::Faccessat2),
+            452 => Some(Sysno::Fchmodat2),
+            _ => None,
+        }
+    }
+
+    pub fn number(self) -> Option<i64> {
+        match self {
+            Sysno::Open => Some(2),
+            Sysno::Newstat => Some(4),
+            Sysno::Newfstat => Some(5),
+            Sysno::Newlstat => Some(6),
+            Sysno::Access => Some(21),
+            Sysno::Mkdir => Some(83),
+            Sysno::Creat => Some(85),
+            Sysno::Link => Some(86),
+            Sysno::Symlink => Some(88),
+            Sysno::Chmod => Some(90),
+            Sysno::Fchmod => Some(91),
+            Sysno::Chown => Some(92),
+            Sysno::Fchown => Some(93),
+            Sysno::Lchown => Some(94),
+            Sysno::Getuid => Some(102),
+            Sysno::Getgid => Some(104),
+            Sysno::Setuid => Some(105),
+            Sysno::Setgid => Some(106),
+            Sysno::Geteuid => Some(107),
+            Sysno::Getegid => Some(108),
+            Sysno::Setreuid => Some(113),
+            Sysno::Setregid => Some(114),
+            Sysno::Getgroups => Some(115),
+            Sysno::Setgroups => Some(116),
+            Sysno::Setresuid => Some(117),
+            Sysno::Getresuid => Some(118),
+            Sysno::Setresgid => Some(119),
+            Sysno::Getresgid => Some(120),
+            Sysno::Setfsuid => Some(122),
+            Sysno::Setfsgid => Some(123),
+            Sysno::Capget => Some(125),
+            Sysno::Capset => Some(126),
+            Sysno::Mknod => Some(133),
+            Sysno::Prctl => Some(157),
+            Sysno::ExitGroup => Some(231),
+            Sysno::Openat => Some(257),
+            Sysno::Mkdirat => Some(258),
+            Sysno::Mknodat => Some(259),
+            Sysno::Fchownat => Some(260),
+            Sysno::Newfstatat => Some(262),
+            Sysno::Linkat => Some(265),
+            Sysno::Symlinkat => Some(266),
+            Sysno::Fchmodat => Some(268),
+            Sysno::Faccessat => Some(269),
+            Sysno::Statx => Some(332),
+            Sysno::Openat2 => Some(437),
+            Sysno::Faccessat2 => Some(439),
+            Sysno::Fchmodat2 => Some(452),
+            _ => None,
+        }
+    }
+
+    /// The syscall's name in this architecture's table
+    pub fn name(self) -> &'static str {
+        match self {
+            Sysno::Open => "open",
+            Sysno::Newstat => "stat",
+            Sysno::Newfstat => "fstat",
+            Sysno::Newlstat => "lstat",
+            Sysno::Access => "access",
+            Sysno::Mkdir => "mkdir",
+            Sysno::Creat => "creat",
+            Sysno::Link => "link",
+            Sysno::Symlink => "symlink",
+            Sysno::Chmod => "chmod",
+            Sysno::Fchmod => "fchmod",
+            Sysno::Chown => "chown",
+            Sysno::Fchown => "fchown",
+            Sysno::Lchown => "lchown",
+            Sysno::Getuid => "getuid",
+            Sysno::Getgid => "getgid",
+            Sysno::Setuid => "setuid",
+            Sysno::Setgid => "setgid",
+            Sysno::Geteuid => "geteuid",
+            Sysno::Getegid => "getegid",
+            Sysno::Setreuid => "setreuid",
+            Sysno::Setregid => "setregid",
+            Sysno::Getgroups => "getgroups",
+            Sysno::Setgroups => "setgroups",
+            Sysno::Setresuid => "setresuid",
+            Sysno::Getresuid => "getresuid",
+            Sysno::Setresgid => "setresgid",
+            Sysno::Getresgid => "getresgid",
+            Sysno::Setfsuid => "setfsuid",
+            Sysno::Setfsgid => "setfsgid",
+            Sysno::Capget => "capget",
+            Sysno::Capset => "capset",
+            Sysno::Mknod => "mknod",
+            Sysno::Prctl => "prctl",
+            Sysno::ExitGroup => "exit_group",
+            Sysno::Openat => "openat",
+            Sysno::Mkdirat => "mkdirat",
+            Sysno::Mknodat => "mknodat",
+            Sysno::Fchownat => "fchownat",
+            Sysno::Newfstatat => "newfstatat",
+            Sysno::Linkat => "linkat",
+            Sysno::Symlinkat => "symlinkat",
+            Sysno::Fchmodat => "fchmodat",
+            Sysno::Faccessat => "faccessat",
+            Sysno::Statx => "statx",
+            Sysno::Openat2 => "openat2",
+            Sysno::Faccessat2 => "faccessat2",
+            Sysno::Fchmodat2 => "fchmodat2",
+            _ => "unknown",
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use crate::types::Result;
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// How an intercepted syscall was handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Disposition {
+    /// Answered from the fake state without reaching the kernel
+    Emulated,
+    
+    /// Run by the kernel with its result adjusted afterwards
+    Inspected,
+    
+    /// Run by the kernel and reported unchanged
+    Passthrough,
+}
+
+/// Decoded arguments of an intercepted syscall; absent ones are left out
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TraceArgs {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fd: Option<i32>,
+    
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uid: Option<u32>,
+    
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gid: Option<u32>,
+    
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<u32>,
+    
+    /// prctl option and its first argument
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub option: Option<i32>,
+    
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arg: Option<u64>,
+}
+
+/// One line of the trace log
+#[derive(Debug, Clone, Serialize)]
+pub struct TraceEvent {
+    pub pid: i32,
+    pub syscall: String,
+    pub args: TraceArgs,
+    pub disposition: Disposition,
+    
+    /// What the caller was told, if minsuki decided it
+    pub fake_result: Option<i64>,
+    
+    /// What the kernel or libc returned, if the call reached it; failures
+    /// are reported as negative errno values in both backends
+    pub real_result: Option<i64>,
+}
+
+/// Append-only JSON lines log of intercepted syscalls, shared by every
+/// traced process of a session
+pub struct TraceLog {
+    file: Mutex<File>,
+}
+
+impl TraceLog {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { file: Mutex::new(file) })
+    }
+    
+    /// Write one event. Each line goes out in a single append so lines
+    /// from concurrent processes do not interleave
+    pub fn record(&self, event: &TraceEvent) {
+        let mut line = match serde_json::to_string(event) {
+            Ok(line) => line,
+            Err(e) => {
+                log::debug!("Cannot serialize trace event: {}", e);
+                return;
+            }
+        };
+        line.push('\n');
+        
+        let mut file = self.file.lock().unwrap();
+        if let Err(e) = file.write_all(line.as_bytes()) {
+            log::debug!("Cannot write trace event: {}", e);
+        }
+    }
+}
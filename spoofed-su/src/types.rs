@@ -1,5 +1,8 @@
+use crate::caps::{CapSet, CapState, Capability, FileCaps};
+use nix::errno::Errno;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::os::unix::fs::MetadataExt;
 use std::path::PathBuf;
 use thiserror::Error;
 
@@ -22,6 +25,9 @@ pub enum MinSukiError {
     
     #[error("Process tracing error: {0}")]
     Ptrace(String),
+    
+    #[error("Invalid capability text: {0}")]
+    Capability(String),
 }
 
 pub type Result<T> = std::result::Result<T, MinSukiError>;
@@ -32,7 +38,7 @@ pub struct FakeMetadata {
     pub uid: u32,
     pub gid: u32,
     pub mode: u32,
-    pub capabilities: Vec<String>,
+    pub capabilities: FileCaps,
 }
 
 impl Default for FakeMetadata {
@@ -41,7 +47,39 @@ impl Default for FakeMetadata {
             uid: 0,  // fake root
             gid: 0,  // fake root group
             mode: 0o755,
-            capabilities: Vec::new(),
+            capabilities: FileCaps::default(),
+        }
+    }
+}
+
+/// What a file looks like on disk, before any emulated change
+impl From<&std::fs::Metadata> for FakeMetadata {
+    fn from(real: &std::fs::Metadata) -> Self {
+        Self {
+            uid: real.uid(),
+            gid: real.gid(),
+            mode: real.mode() & 0o7777,
+            capabilities: FileCaps::default(),
+        }
+    }
+}
+
+impl FakeMetadata {
+    /// Apply a chown, where an id of -1 leaves that id unchanged. Like the
+    /// kernel, drop set-user-ID and (if group-executable) set-group-ID from
+    /// anything but a directory, even for root
+    pub fn chown(&mut self, uid: u32, gid: u32, is_dir: bool) {
+        if uid != u32::MAX {
+            self.uid = uid;
+        }
+        if gid != u32::MAX {
+            self.gid = gid;
+        }
+        if !is_dir && (uid != u32::MAX || gid != u32::MAX) {
+            self.mode &= !libc::S_ISUID;
+            if self.mode & libc::S_IXGRP != 0 {
+                self.mode &= !libc::S_ISGID;
+            }
         }
     }
 }
@@ -50,8 +88,17 @@ impl Default for FakeMetadata {
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct FakeState {
     /// Map of file paths to their fake metadata
+    #[serde(with = "path_keys")]
     pub files: HashMap<PathBuf, FakeMetadata>,
     
+    /// The identity every process of a session starts with. Changes a
+    /// process makes to its own identity are never written back
+    pub credentials: Credentials,
+}
+
+/// The fake identity of one process
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct Credentials {
     /// Current fake UID
     pub current_uid: u32,
     
@@ -64,24 +111,48 @@ pub struct FakeState {
     /// Fake effective GID
     pub effective_gid: u32,
     
-    /// Process capabilities
-    pub capabilities: Vec<String>,
+    /// Process capability sets
+    pub capabilities: CapState,
+    
+    /// Fake saved set-user-ID
+    pub saved_uid: u32,
+    
+    /// Fake saved set-group-ID
+    pub saved_gid: u32,
+    
+    /// Fake filesystem UID
+    pub fs_uid: u32,
+    
+    /// Fake filesystem GID
+    pub fs_gid: u32,
+    
+    /// Fake supplementary group IDs
+    pub groups: Vec<u32>,
+    
+    /// Whether the permitted capabilities survive leaving UID 0 behind,
+    /// as set with prctl(PR_SET_KEEPCAPS)
+    pub keep_caps: bool,
 }
 
 impl FakeState {
     pub fn new() -> Self {
         Self {
             files: HashMap::new(),
-            current_uid: unsafe { libc::getuid() },
-            current_gid: unsafe { libc::getgid() },
-            effective_uid: 0, // Emulate root
-            effective_gid: 0,
-            capabilities: vec![
-                "CAP_CHOWN".to_string(),
-                "CAP_DAC_OVERRIDE".to_string(),
-                "CAP_FOWNER".to_string(),
-                "CAP_NET_BIND_SERVICE".to_string(),
-            ],
+            // Plain root: every id 0, so shells that drop a set-user-ID
+            // identity with setuid(getuid()) stay root
+            credentials: Credentials {
+                current_uid: 0,
+                current_gid: 0,
+                effective_uid: 0,
+                effective_gid: 0,
+                capabilities: CapState::root(),
+                saved_uid: 0,
+                saved_gid: 0,
+                fs_uid: 0,
+                fs_gid: 0,
+                groups: Vec::new(),
+                keep_caps: false,
+            },
         }
     }
     
@@ -104,9 +175,250 @@ impl FakeState {
         metadata.mode = mode;
     }
     
+    pub fn setcap(&mut self, path: PathBuf, capabilities: FileCaps) {
+        let metadata = self.files.entry(path).or_insert_with(FakeMetadata::default);
+        metadata.capabilities = capabilities;
+    }
+}
+
+impl Credentials {
     pub fn is_root(&self) -> bool {
         self.effective_uid == 0
     }
+    
+    /// Whether `capability` is in the fake effective set
+    pub fn capable(&self, capability: Capability) -> bool {
+        self.capabilities.effective.contains(capability)
+    }
+    
+    /// setuid(2): with CAP_SETUID every UID changes; without it only the
+    /// effective UID, and only back to the real or saved one
+    pub fn setuid(&mut self, uid: u32) -> Result<()> {
+        if self.capable(Capability::Setuid) {
+            self.assign_uids(uid, uid, uid);
+        } else if uid == self.current_uid || uid == self.saved_uid {
+            self.assign_uids(u32::MAX, uid, u32::MAX);
+        } else {
+            return Err(MinSukiError::PermissionDenied);
+        }
+        Ok(())
+    }
+    
+    /// setreuid(2): without CAP_SETUID the real UID may only become the
+    /// real or effective one, and the effective UID any of the three. The
+    /// saved set-user-ID follows the new effective UID when the real UID is
+    /// set, or the effective UID is set to anything but the previous real UID
+    pub fn setreuid(&mut self, ruid: u32, euid: u32) -> Result<()> {
+        if !self.capable(Capability::Setuid) {
+            let ruid_ok = ruid == u32::MAX || ruid == self.current_uid || ruid == self.effective_uid;
+            if !ruid_ok || !self.holds_uid(euid) {
+                return Err(MinSukiError::PermissionDenied);
+            }
+        }
+        
+        let save = ruid != u32::MAX || (euid != u32::MAX && euid != self.current_uid);
+        let suid = if save { self.resolve(euid, self.effective_uid) } else { u32::MAX };
+        self.assign_uids(ruid, euid, suid);
+        Ok(())
+    }
+    
+    /// setresuid(2): an id of -1 leaves that id unchanged. Without
+    /// CAP_SETUID each id may only become one of the current three
+    pub fn setresuid(&mut self, ruid: u32, euid: u32, suid: u32) -> Result<()> {
+        if !self.capable(Capability::Setuid) && ![ruid, euid, suid].iter().all(|id| self.holds_uid(*id)) {
+            return Err(MinSukiError::PermissionDenied);
+        }
+        self.assign_uids(ruid, euid, suid);
+        Ok(())
+    }
+    
+    /// setfsuid(2), returning the previous filesystem UID. Without
+    /// CAP_SETUID the change silently fails unless the new id is one the
+    /// process already holds
+    pub fn setfsuid(&mut self, uid: u32) -> u32 {
+        let old = self.fs_uid;
+        if uid != u32::MAX && (self.capable(Capability::Setuid) || self.holds_uid(uid) || uid == self.fs_uid) {
+            self.fs_uid = uid;
+            self.capabilities.fix_setfsuid(old, uid);
+        }
+        old
+    }
+    
+    /// setgid(2), with the rules of setuid and CAP_SETGID
+    pub fn setgid(&mut self, gid: u32) -> Result<()> {
+        if self.capable(Capability::Setgid) {
+            self.assign_gids(gid, gid, gid);
+        } else if gid == self.current_gid || gid == self.saved_gid {
+            self.assign_gids(u32::MAX, gid, u32::MAX);
+        } else {
+            return Err(MinSukiError::PermissionDenied);
+        }
+        Ok(())
+    }
+    
+    /// setregid(2), with the rules of setreuid and CAP_SETGID
+    pub fn setregid(&mut self, rgid: u32, egid: u32) -> Result<()> {
+        if !self.capable(Capability::Setgid) {
+            let rgid_ok = rgid == u32::MAX || rgid == self.current_gid || rgid == self.effective_gid;
+            if !rgid_ok || !self.holds_gid(egid) {
+                return Err(MinSukiError::PermissionDenied);
+            }
+        }
+        
+        let save = rgid != u32::MAX || (egid != u32::MAX && egid != self.current_gid);
+        let sgid = if save { self.resolve(egid, self.effective_gid) } else { u32::MAX };
+        self.assign_gids(rgid, egid, sgid);
+        Ok(())
+    }
+    
+    /// setresgid(2), with the rules of setresuid and CAP_SETGID
+    pub fn setresgid(&mut self, rgid: u32, egid: u32, sgid: u32) -> Result<()> {
+        if !self.capable(Capability::Setgid) && ![rgid, egid, sgid].iter().all(|id| self.holds_gid(*id)) {
+            return Err(MinSukiError::PermissionDenied);
+        }
+        self.assign_gids(rgid, egid, sgid);
+        Ok(())
+    }
+    
+    /// setfsgid(2), with the rules of setfsuid and CAP_SETGID
+    pub fn setfsgid(&mut self, gid: u32) -> u32 {
+        let old = self.fs_gid;
+        if gid != u32::MAX && (self.capable(Capability::Setgid) || self.holds_gid(gid) || gid == self.fs_gid) {
+            self.fs_gid = gid;
+        }
+        old
+    }
+    
+    /// setgroups(2), which always requires CAP_SETGID
+    pub fn setgroups(&mut self, groups: Vec<u32>) -> Result<()> {
+        if !self.capable(Capability::Setgid) {
+            return Err(MinSukiError::PermissionDenied);
+        }
+        self.groups = groups;
+        Ok(())
+    }
+    
+    /// execve(2) of a file with the given metadata. A set-user-ID bit, and
+    /// a set-group-ID bit on a group-executable file, switch the effective
+    /// ids to the file's owner; the saved ids then follow the effective
+    /// ones and the capability sets go through the exec transformation
+    pub fn exec(&mut self, file: &FakeMetadata) {
+        if file.mode & libc::S_ISUID != 0 {
+            self.effective_uid = file.uid;
+        }
+        if file.mode & libc::S_ISGID != 0 && file.mode & libc::S_IXGRP != 0 {
+            self.effective_gid = file.gid;
+        }
+        let setid = self.effective_uid != self.current_uid || self.effective_gid != self.current_gid;
+        
+        self.saved_uid = self.effective_uid;
+        self.saved_gid = self.effective_gid;
+        self.fs_uid = self.effective_uid;
+        self.fs_gid = self.effective_gid;
+        self.capabilities = self.capabilities.exec(&file.capabilities, setid, self.current_uid, self.effective_uid);
+        self.keep_caps = false;
+    }
+    
+    /// The capability operations of prctl(2), applied to the fake sets.
+    /// Gives the call's return value, or None for an option that has
+    /// nothing to do with capabilities
+    pub fn prctl(&mut self, option: i32, args: [u64; 4]) -> Option<nix::Result<i64>> {
+        let capability = |arg: u64| u32::try_from(arg).ok().and_then(Capability::from_number).ok_or(Errno::EINVAL);
+        
+        let result = match option {
+            libc::PR_CAPBSET_READ => capability(args[0]).map(|cap| self.capabilities.bounding.contains(cap) as i64),
+            libc::PR_CAPBSET_DROP => {
+                if !self.capable(Capability::Setpcap) {
+                    return Some(Err(Errno::EPERM));
+                }
+                capability(args[0]).map(|cap| {
+                    self.capabilities.bounding.remove(cap);
+                    0
+                })
+            }
+            libc::PR_GET_KEEPCAPS => Ok(self.keep_caps as i64),
+            libc::PR_SET_KEEPCAPS => match args[0] {
+                0 | 1 => {
+                    self.keep_caps = args[0] == 1;
+                    Ok(0)
+                }
+                _ => Err(Errno::EINVAL),
+            },
+            libc::PR_CAP_AMBIENT => self.prctl_ambient(args),
+            _ => return None,
+        };
+        Some(result)
+    }
+    
+    /// prctl(PR_CAP_AMBIENT): only a capability that is both permitted and
+    /// inheritable may be raised
+    fn prctl_ambient(&mut self, args: [u64; 4]) -> nix::Result<i64> {
+        let sets = &mut self.capabilities;
+        if args[0] == libc::PR_CAP_AMBIENT_CLEAR_ALL as u64 {
+            if args[1..].iter().any(|arg| *arg != 0) {
+                return Err(Errno::EINVAL);
+            }
+            sets.ambient = CapSet::EMPTY;
+            return Ok(0);
+        }
+        
+        let cap = u32::try_from(args[1]).ok().and_then(Capability::from_number);
+        let cap = match cap {
+            Some(cap) if args[2] == 0 && args[3] == 0 => cap,
+            _ => return Err(Errno::EINVAL),
+        };
+        match args[0] as i32 {
+            libc::PR_CAP_AMBIENT_IS_SET => Ok(sets.ambient.contains(cap) as i64),
+            libc::PR_CAP_AMBIENT_RAISE => {
+                if !sets.permitted.contains(cap) || !sets.inheritable.contains(cap) {
+                    return Err(Errno::EPERM);
+                }
+                sets.ambient.insert(cap);
+                Ok(0)
+            }
+            libc::PR_CAP_AMBIENT_LOWER => {
+                sets.ambient.remove(cap);
+                Ok(0)
+            }
+            _ => Err(Errno::EINVAL),
+        }
+    }
+    
+    /// Whether an unprivileged process may switch to `uid`; -1 is a no-op
+    fn holds_uid(&self, uid: u32) -> bool {
+        uid == u32::MAX || uid == self.current_uid || uid == self.effective_uid || uid == self.saved_uid
+    }
+    
+    fn holds_gid(&self, gid: u32) -> bool {
+        gid == u32::MAX || gid == self.current_gid || gid == self.effective_gid || gid == self.saved_gid
+    }
+    
+    /// The id a -1 argument stands for
+    fn resolve(&self, id: u32, current: u32) -> u32 {
+        if id == u32::MAX { current } else { id }
+    }
+    
+    /// Change the UIDs without any checks. The filesystem UID follows the
+    /// effective one, and the capability sets follow UID 0 the way the
+    /// kernel's setuid fixup has them do
+    fn assign_uids(&mut self, ruid: u32, euid: u32, suid: u32) {
+        let old = [self.current_uid, self.effective_uid, self.saved_uid];
+        
+        self.current_uid = self.resolve(ruid, self.current_uid);
+        self.effective_uid = self.resolve(euid, self.effective_uid);
+        self.saved_uid = self.resolve(suid, self.saved_uid);
+        self.fs_uid = self.effective_uid;
+        
+        let new = [self.current_uid, self.effective_uid, self.saved_uid];
+        self.capabilities.fix_setuid(old, new, self.keep_caps);
+    }
+    
+    fn assign_gids(&mut self, rgid: u32, egid: u32, sgid: u32) {
+        self.current_gid = self.resolve(rgid, self.current_gid);
+        self.effective_gid = self.resolve(egid, self.effective_gid);
+        self.saved_gid = self.resolve(sgid, self.saved_gid);
+        self.fs_gid = self.effective_gid;
+    }
 }
 
 /// System call interception mode
@@ -122,6 +434,13 @@ pub enum InterceptionMode {
     Seccomp,
 }
 
+/// Environment variable carrying a process's fake identity across execve
+pub const CREDENTIALS_ENV: &str = "MINSUKI_CREDENTIALS";
+
+/// Environment variable naming the fake root filesystem whose etc/passwd
+/// and etc/group answer user and group lookups
+pub const ROOTFS_ENV: &str = "MINSUKI_ROOTFS";
+
 /// Configuration for MinSuki
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -142,4 +461,125 @@ impl Default for Config {
             denied_paths: vec![PathBuf::from("/etc/shadow")],
         }
     }
-}
\ No newline at end of file
+}
+
+/// Serialize path keys as raw bytes, so paths that are not valid UTF-8
+/// survive a round trip through the state file
+mod path_keys {
+    use super::FakeMetadata;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::collections::HashMap;
+    use std::ffi::OsString;
+    use std::os::unix::ffi::{OsStrExt, OsStringExt};
+    use std::path::PathBuf;
+    
+    pub fn serialize<S: Serializer>(files: &HashMap<PathBuf, FakeMetadata>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(files.iter().map(|(path, meta)| (path.as_os_str().as_bytes(), meta)))
+    }
+    
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HashMap<PathBuf, FakeMetadata>, D::Error> {
+        let files = HashMap::<Vec<u8>, FakeMetadata>::deserialize(deserializer)?;
+        Ok(files
+            .into_iter()
+            .map(|(path, meta)| (PathBuf::from(OsString::from_vec(path)), meta))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_credential_transitions() {
+        let mut creds = Credentials { capabilities: CapState::root(), ..Credentials::default() };
+        creds.setresuid(1000, 0, 0).unwrap();
+        
+        // Swapping real and effective UID saves the new effective one
+        creds.setreuid(0, 1000).unwrap();
+        assert_eq!((creds.current_uid, creds.effective_uid, creds.saved_uid), (0, 1000, 1000));
+        assert_eq!(creds.fs_uid, 1000);
+        
+        // An unprivileged setuid only changes the effective UID
+        creds.setuid(0).unwrap();
+        assert_eq!((creds.current_uid, creds.effective_uid, creds.saved_uid), (0, 0, 1000));
+        
+        // A privileged one changes all of them, for good
+        creds.setuid(5).unwrap();
+        assert_eq!((creds.current_uid, creds.effective_uid, creds.saved_uid, creds.fs_uid), (5, 5, 5, 5));
+        assert!(creds.setuid(0).is_err());
+        assert!(creds.setresuid(u32::MAX, 0, u32::MAX).is_err());
+        
+        // Without CAP_SETGID setfsgid only switches to ids already held
+        assert_eq!(creds.setfsgid(7), 0);
+        assert_eq!(creds.setfsgid(u32::MAX), 0);
+    }
+    
+    #[test]
+    fn test_shell_keeps_fake_root() {
+        // Shells drop a set-user-ID identity with setuid(getuid()), which
+        // must leave the starting identity root
+        let mut creds = FakeState::new().credentials;
+        let uid = creds.current_uid;
+        creds.setuid(uid).unwrap();
+        assert_eq!((creds.current_uid, creds.effective_uid, creds.saved_uid, creds.fs_uid), (0, 0, 0, 0));
+        assert!(creds.capable(Capability::Chown));
+        
+        let gid = creds.current_gid;
+        creds.setgid(gid).unwrap();
+        assert_eq!(creds.effective_gid, 0);
+    }
+    
+    #[test]
+    fn test_exec_setuid() {
+        let mut creds = Credentials { current_uid: 1000, effective_uid: 1000, saved_uid: 1000, ..Credentials::default() };
+        let setuid_root = FakeMetadata { mode: 0o4755, ..FakeMetadata::default() };
+        
+        creds.exec(&setuid_root);
+        assert_eq!((creds.current_uid, creds.effective_uid, creds.saved_uid), (1000, 0, 0));
+        assert_eq!(creds.capabilities.effective, CapSet::EMPTY);
+        
+        // With a full bounding set, a set-user-ID root program gets every
+        // capability
+        creds.capabilities.bounding = CapSet::full();
+        creds.exec(&setuid_root);
+        assert_eq!(creds.capabilities.effective, CapSet::full());
+        
+        // and gives them up again along with the ids for a plain program
+        creds.setuid(1000).unwrap();
+        creds.exec(&FakeMetadata::default());
+        assert_eq!((creds.effective_uid, creds.saved_uid), (1000, 1000));
+        assert!(creds.capabilities.permitted.is_empty());
+    }
+    
+    #[test]
+    fn test_capability_prctl() {
+        let mut creds = Credentials { capabilities: CapState::root(), ..Credentials::default() };
+        let cap = |cap: Capability| cap as u64;
+        
+        assert_eq!(creds.prctl(libc::PR_CAPBSET_DROP, [cap(Capability::NetRaw), 0, 0, 0]), Some(Ok(0)));
+        assert_eq!(creds.prctl(libc::PR_CAPBSET_READ, [cap(Capability::NetRaw), 0, 0, 0]), Some(Ok(0)));
+        assert_eq!(creds.prctl(libc::PR_CAPBSET_READ, [99, 0, 0, 0]), Some(Err(Errno::EINVAL)));
+        assert_eq!(creds.prctl(libc::PR_SET_NAME, [0, 0, 0, 0]), None);
+        
+        // Keeping capabilities across setuid keeps them permitted only
+        assert_eq!(creds.prctl(libc::PR_SET_KEEPCAPS, [1, 0, 0, 0]), Some(Ok(0)));
+        creds.setuid(1000).unwrap();
+        assert!(creds.capabilities.permitted.contains(Capability::Chown));
+        assert!(creds.capabilities.effective.is_empty());
+        
+        // Ambient capabilities must be permitted and inheritable
+        let raise = [libc::PR_CAP_AMBIENT_RAISE as u64, cap(Capability::Chown), 0, 0];
+        assert_eq!(creds.prctl(libc::PR_CAP_AMBIENT, raise), Some(Err(Errno::EPERM)));
+        let mut data = creds.capabilities.user_data(2);
+        data[2] |= 1 << Capability::Chown as u32;
+        creds.capabilities.capset(&data).unwrap();
+        assert_eq!(creds.prctl(libc::PR_CAP_AMBIENT, raise), Some(Ok(0)));
+        
+        // A capability capset drops from the permitted set is gone for good
+        data[1] &= !(1 << Capability::NetRaw as u32);
+        creds.capabilities.capset(&data).unwrap();
+        data[1] |= 1 << Capability::NetRaw as u32;
+        assert!(creds.capabilities.capset(&data).is_err());
+    }
+}
@@ -0,0 +1,137 @@
+use std::path::Path;
+
+/// A line of an /etc/passwd file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct User {
+    pub name: String,
+    pub passwd: String,
+    pub uid: u32,
+    pub gid: u32,
+    pub gecos: String,
+    pub dir: String,
+    pub shell: String,
+}
+
+/// A line of an /etc/group file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group {
+    pub name: String,
+    pub passwd: String,
+    pub gid: u32,
+    pub members: Vec<String>,
+}
+
+impl User {
+    /// Parse `name:passwd:uid:gid:gecos:dir:shell`
+    pub fn parse(line: &str) -> Option<Self> {
+        let fields: Vec<&str> = line.split(':').collect();
+        if fields.len() != 7 {
+            return None;
+        }
+        Some(Self {
+            name: fields[0].to_string(),
+            passwd: fields[1].to_string(),
+            uid: fields[2].parse().ok()?,
+            gid: fields[3].parse().ok()?,
+            gecos: fields[4].to_string(),
+            dir: fields[5].to_string(),
+            shell: fields[6].to_string(),
+        })
+    }
+}
+
+impl Group {
+    /// Parse `name:passwd:gid:member,member,...`
+    pub fn parse(line: &str) -> Option<Self> {
+        let fields: Vec<&str> = line.split(':').collect();
+        if fields.len() != 4 {
+            return None;
+        }
+        Some(Self {
+            name: fields[0].to_string(),
+            passwd: fields[1].to_string(),
+            gid: fields[2].parse().ok()?,
+            members: fields[3].split(',').filter(|m| !m.is_empty()).map(str::to_string).collect(),
+        })
+    }
+}
+
+/// The user and group databases of a fake root filesystem, such as the one
+/// envspoof creates
+#[derive(Debug, Clone, Default)]
+pub struct UserDb {
+    pub users: Vec<User>,
+    pub groups: Vec<Group>,
+}
+
+impl UserDb {
+    /// Read `etc/passwd` and `etc/group` under `root`. A missing file is an
+    /// empty database, and lines that do not parse are skipped like NSS does
+    pub fn load(root: &Path) -> Self {
+        let read = |name: &str| std::fs::read_to_string(root.join("etc").join(name)).unwrap_or_default();
+        Self::parse(&read("passwd"), &read("group"))
+    }
+    
+    pub fn parse(passwd: &str, group: &str) -> Self {
+        let lines = |text: &str| {
+            text.lines()
+                .map(str::trim_end)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+        Self {
+            users: lines(passwd).iter().filter_map(|line| User::parse(line)).collect(),
+            groups: lines(group).iter().filter_map(|line| Group::parse(line)).collect(),
+        }
+    }
+    
+    pub fn user_by_uid(&self, uid: u32) -> Option<&User> {
+        self.users.iter().find(|user| user.uid == uid)
+    }
+    
+    pub fn user_by_name(&self, name: &str) -> Option<&User> {
+        self.users.iter().find(|user| user.name == name)
+    }
+    
+    pub fn group_by_gid(&self, gid: u32) -> Option<&Group> {
+        self.groups.iter().find(|group| group.gid == gid)
+    }
+    
+    pub fn group_by_name(&self, name: &str) -> Option<&Group> {
+        self.groups.iter().find(|group| group.name == name)
+    }
+    
+    /// The groups of `user` as getgrouplist reports them: `group` first,
+    /// then every group listing the user as a member, each once
+    pub fn group_list(&self, user: &str, group: u32) -> Vec<u32> {
+        let mut list = vec![group];
+        for entry in &self.groups {
+            if entry.members.iter().any(|member| member == user) && !list.contains(&entry.gid) {
+                list.push(entry.gid);
+            }
+        }
+        list
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_userdb_lookups() {
+        let db = UserDb::parse(
+            "root:x:0:0:root:/root:/bin/bash\n# comment\nbroken line\nmilo:x:1000:1000::/home/milo:/bin/sh\n",
+            "root:x:0:\nwheel:x:10:root,milo\nmilo:x:1000:\naudio:x:29:milo\n",
+        );
+        
+        assert_eq!(db.users.len(), 2);
+        assert_eq!(db.user_by_uid(1000).map(|u| u.name.as_str()), Some("milo"));
+        assert_eq!(db.user_by_name("root").map(|u| u.shell.as_str()), Some("/bin/bash"));
+        assert_eq!(db.group_by_gid(10).map(|g| g.members.len()), Some(2));
+        assert!(db.group_by_name("root").unwrap().members.is_empty());
+        assert_eq!(db.group_list("milo", 1000), vec![1000, 10, 29]);
+        assert_eq!(db.group_list("root", 10), vec![10]);
+    }
+}